@@ -0,0 +1,78 @@
+# MNIST-family (IDX format) Dataset Auto Downloader
+# Usage: .\scripts\download_idx.ps1 -Dataset fashion_mnist   (mnist | fashion_mnist | kmnist | emnist_letters)
+
+param(
+    [ValidateSet("mnist", "fashion_mnist", "kmnist", "emnist_letters")]
+    [string]$Dataset = "fashion_mnist"
+)
+
+$sources = @{
+    "mnist"         = @{ Dir = "datasets/mnist"; Base = "https://ossci-datasets.s3.amazonaws.com/mnist" }
+    "fashion_mnist" = @{ Dir = "datasets/fashion-mnist"; Base = "http://fashion-mnist.s3-website.eu-central-1.amazonaws.com" }
+    "kmnist"        = @{ Dir = "datasets/kmnist"; Base = "http://codh.rois.ac.jp/kmnist/dataset/kmnist" }
+}
+$files = @(
+    "train-images-idx3-ubyte.gz",
+    "train-labels-idx1-ubyte.gz",
+    "t10k-images-idx3-ubyte.gz",
+    "t10k-labels-idx1-ubyte.gz"
+)
+
+Write-Host "=== $Dataset Dataset Auto Download ===" -ForegroundColor Green
+
+if ($Dataset -eq "emnist_letters") {
+    # EMNIST is distributed as one zip containing every split; keep only the letters files (.gz is read directly)
+    $dataDir = "datasets/emnist-letters"
+    $url = "https://biometrics.nist.gov/cs_links/EMNIST/gzip.zip"
+    $zipFile = "datasets/emnist-gzip.zip"
+    New-Item -ItemType Directory -Force -Path $dataDir | Out-Null
+
+    if (!(Test-Path $zipFile)) {
+        Write-Host "Downloading EMNIST (about 530 MB)..." -ForegroundColor Green
+        Write-Host "URL: $url" -ForegroundColor Cyan
+        try {
+            Invoke-WebRequest -Uri $url -OutFile $zipFile -UseBasicParsing
+        } catch {
+            Write-Host "Download error: $_" -ForegroundColor Red
+            exit 1
+        }
+    } else {
+        Write-Host "Already downloaded: $zipFile" -ForegroundColor Yellow
+    }
+
+    Write-Host "Extracting letters files..." -ForegroundColor Green
+    Add-Type -AssemblyName System.IO.Compression.FileSystem
+    $zip = [System.IO.Compression.ZipFile]::OpenRead((Resolve-Path $zipFile))
+    foreach ($entry in $zip.Entries | Where-Object { $_.Name -like "emnist-letters-*-ubyte.gz" }) {
+        [System.IO.Compression.ZipFileExtensions]::ExtractToFile($entry, (Join-Path $dataDir $entry.Name), $true)
+    }
+    $zip.Dispose()
+} else {
+    $dataDir = $sources[$Dataset].Dir
+    $base = $sources[$Dataset].Base
+    New-Item -ItemType Directory -Force -Path $dataDir | Out-Null
+
+    foreach ($file in $files) {
+        $path = "$dataDir/$file"
+        if (Test-Path $path) {
+            Write-Host "Already downloaded: $path" -ForegroundColor Yellow
+            continue
+        }
+        Write-Host "Downloading $base/$file" -ForegroundColor Cyan
+        try {
+            Invoke-WebRequest -Uri "$base/$file" -OutFile $path -UseBasicParsing
+        } catch {
+            Write-Host "Download error: $_" -ForegroundColor Red
+            exit 1
+        }
+    }
+}
+
+Write-Host "`n=== Data Files ===" -ForegroundColor Green
+foreach ($file in Get-ChildItem $dataDir -Name) {
+    $size = (Get-Item (Join-Path $dataDir $file)).Length
+    Write-Host "  $file ($([math]::Round($size/1KB, 1)) KB)" -ForegroundColor White
+}
+
+Write-Host "`nReady! You can start training:" -ForegroundColor Green
+Write-Host "  cargo run -- train --dataset $Dataset --epochs 10" -ForegroundColor Cyan
@@ -0,0 +1,235 @@
+// src/augment.rs
+use crate::config::{AugmentationConfig, ColorJitterConfig, CutoutConfig};
+use burn::{prelude::*, tensor::Distribution};
+
+/// 学習バッチ用のデータ拡張
+///
+/// 乱数はバックエンドの乱数 (`B::seed` で固定) から引くため、
+/// 同じシードなら同じ拡張になり、チェックポイントからの再開後も再現される
+/// (エポック途中の再開では、読み飛ばすバッチの分を引いた後に再シードする: `train::train_batches`)。
+#[derive(Clone, Debug)]
+pub struct Augmenter {
+    config: AugmentationConfig,
+}
+
+impl Augmenter {
+    /// 拡張が1つも有効でなければ None
+    pub fn new(config: &AugmentationConfig) -> Option<Self> {
+        config.is_enabled().then(|| Self {
+            config: config.clone(),
+        })
+    }
+
+    /// 正規化前の 0-255 の画素 [N,C,H,W] に拡張を適用
+    pub fn apply<B: Backend>(&self, images: Tensor<B, 4>) -> Tensor<B, 4> {
+        let mut images = images;
+        if let Some(padding) = self.config.random_crop_padding.filter(|&p| p > 0) {
+            images = random_crop(images, padding);
+        }
+        if self.config.horizontal_flip {
+            images = random_flip(images);
+        }
+        if let Some(jitter) = &self.config.color_jitter {
+            images = color_jitter(images, jitter);
+        }
+        if let Some(cutout_config) = &self.config.cutout {
+            images = cutout(images, cutout_config);
+        }
+        images
+    }
+}
+
+/// バックエンドの乱数 (NdArray はプロセス全体で1つ) を使うテストを直列にする
+#[cfg(all(test, feature = "ndarray"))]
+pub(crate) fn lock_backend_rng() -> std::sync::MutexGuard<'static, ()> {
+    static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 画像ごとの乱数 [N,1,1,1]
+fn per_image<B: Backend>(n: usize, distribution: Distribution, device: &B::Device) -> Tensor<B, 4> {
+    Tensor::random([n, 1, 1, 1], distribution, device)
+}
+
+/// 画像ごとの整数乱数 0..high (CPU側で切り出し位置に使う)
+fn random_indices<B: Backend>(n: usize, high: usize, device: &B::Device) -> Vec<usize> {
+    Tensor::<B, 1>::random([n], Distribution::Uniform(0.0, high as f64), device)
+        .into_data()
+        .iter::<f32>()
+        .map(|v| (v as usize).min(high - 1))
+        .collect()
+}
+
+fn random_crop<B: Backend>(images: Tensor<B, 4>, padding: usize) -> Tensor<B, 4> {
+    let [n, c, h, w] = images.dims();
+    let device = images.device();
+    let padded = images.pad((padding, padding, padding, padding), 0.0);
+
+    let ys = random_indices::<B>(n, 2 * padding + 1, &device);
+    let xs = random_indices::<B>(n, 2 * padding + 1, &device);
+    let crops = (0..n)
+        .map(|i| {
+            padded
+                .clone()
+                .slice([i..i + 1, 0..c, ys[i]..ys[i] + h, xs[i]..xs[i] + w])
+        })
+        .collect();
+    Tensor::cat(crops, 0)
+}
+
+fn random_flip<B: Backend>(images: Tensor<B, 4>) -> Tensor<B, 4> {
+    let [n, _, _, _] = images.dims();
+    let flip = per_image::<B>(n, Distribution::Bernoulli(0.5), &images.device());
+    let flipped = images.clone().flip([3]);
+    images.clone() + (flipped - images) * flip
+}
+
+fn color_jitter<B: Backend>(images: Tensor<B, 4>, config: &ColorJitterConfig) -> Tensor<B, 4> {
+    let [n, c, _, _] = images.dims();
+    let device = images.device();
+    let factor = |strength: f32| {
+        let s = strength.clamp(0.0, 1.0) as f64;
+        per_image::<B>(n, Distribution::Uniform(1.0 - s, 1.0 + s), &device)
+    };
+
+    let mut images = images;
+    if config.brightness > 0.0 {
+        images = images * factor(config.brightness);
+    }
+    if config.contrast > 0.0 {
+        let mean = grayscale(images.clone()).mean_dim(2).mean_dim(3); // [N,1,1,1]
+        images = (images - mean.clone()) * factor(config.contrast) + mean;
+    }
+    if config.saturation > 0.0 && c == 3 {
+        let gray = grayscale(images.clone()); // [N,1,H,W]
+        images = (images - gray.clone()) * factor(config.saturation) + gray;
+    }
+    images.clamp(0.0, 255.0)
+}
+
+/// [N,C,H,W] -> [N,1,H,W] (ITU-R BT.601)
+fn grayscale<B: Backend>(images: Tensor<B, 4>) -> Tensor<B, 4> {
+    let [_, c, _, _] = images.dims();
+    if c != 3 {
+        return images.mean_dim(1);
+    }
+    let weights = Tensor::<B, 1>::from_floats([0.299, 0.587, 0.114], &images.device())
+        .reshape([1, 3, 1, 1]);
+    (images * weights).sum_dim(1)
+}
+
+fn cutout<B: Backend>(images: Tensor<B, 4>, config: &CutoutConfig) -> Tensor<B, 4> {
+    let [n, _, h, w] = images.dims();
+    let device = images.device();
+    let size = config.size as f32;
+    // 開始位置も整数にする (奇数の一辺で半端になるとマスクの縁が 0.5 になる)
+    let half = (config.size / 2) as f32;
+
+    // 正方形の中心を画像内から選び、はみ出した部分は切り捨てる
+    let top = per_image::<B>(n, Distribution::Uniform(0.0, h as f64), &device).floor() - half;
+    let left = per_image::<B>(n, Distribution::Uniform(0.0, w as f64), &device).floor() - half;
+    // 座標は画像枚数ぶん複製しておく (両辺がブロードキャストされる演算は ndarray で結果が崩れるため)
+    let rows = Tensor::<B, 1, Int>::arange(0..h as i64, &device)
+        .float()
+        .reshape([1, 1, h, 1])
+        .repeat_dim(0, n);
+    let cols = Tensor::<B, 1, Int>::arange(0..w as i64, &device)
+        .float()
+        .reshape([1, 1, 1, w])
+        .repeat_dim(0, n);
+
+    // 整数座標なので 0 <= offset < size を 0/1 で表す
+    let inside = |pos: Tensor<B, 4>, start: Tensor<B, 4>| {
+        let offset = pos - start;
+        (offset.clone() + 1.0).clamp(0.0, 1.0) * (offset.neg() + size).clamp(0.0, 1.0)
+    };
+    let apply = per_image::<B>(n, Distribution::Bernoulli(config.probability), &device);
+    let erase = inside(rows, top) * inside(cols, left) * apply; // [N,1,H,W]
+
+    images * (erase.neg() + 1.0)
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use burn::backend::NdArray;
+
+    type B = NdArray;
+
+    /// 画素ごとに異なる値の [N,C,H,W]
+    fn images(n: usize, c: usize, h: usize, w: usize) -> Tensor<B, 4> {
+        Tensor::<B, 1, Int>::arange(0..(n * c * h * w) as i64, &Default::default())
+            .float()
+            .reshape([n, c, h, w])
+    }
+
+    /// 同じシードで2回適用して同じ結果になることを確認し、その結果を返す
+    fn twice(config: AugmentationConfig, input: Tensor<B, 4>) -> Vec<f32> {
+        let augmenter = Augmenter::new(&config).expect("enabled");
+        let _rng = lock_backend_rng();
+        B::seed(7);
+        let a = augmenter.apply(input.clone()).into_data();
+        B::seed(7);
+        let b = augmenter.apply(input).into_data();
+        a.assert_eq(&b, true);
+        a.to_vec().unwrap()
+    }
+
+    #[test]
+    fn flip_and_crop_are_deterministic_under_a_fixed_seed() {
+        let input = images(8, 1, 4, 4);
+        let values = input.clone().into_data().to_vec::<f32>().unwrap();
+        let flipped = input.clone().flip([3]).into_data().to_vec::<f32>().unwrap();
+
+        let config = AugmentationConfig {
+            horizontal_flip: true,
+            ..Default::default()
+        };
+        let out = twice(config, input.clone());
+        // 画像ごとに元のままか左右反転のどちらか
+        for (i, image) in out.chunks(16).enumerate() {
+            let range = i * 16..(i + 1) * 16;
+            assert!(image == &values[range.clone()] || image == &flipped[range], "image {i}");
+        }
+
+        let config = AugmentationConfig {
+            random_crop_padding: Some(1),
+            ..Default::default()
+        };
+        let out = twice(config, input);
+        // 切り出し位置は 3x3 通りのどれか (はみ出した部分は 0)
+        let padded = images(8, 1, 4, 4).pad((1, 1, 1, 1), 0.0);
+        for (i, image) in out.chunks(16).enumerate() {
+            let found = (0..3).any(|y| {
+                (0..3).any(|x| {
+                    let crop = padded.clone().slice([i..i + 1, 0..1, y..y + 4, x..x + 4]);
+                    crop.into_data().to_vec::<f32>().unwrap() == image
+                })
+            });
+            assert!(found, "image {i}");
+        }
+    }
+
+    #[test]
+    fn cutout_is_deterministic_and_binary_for_odd_sizes() {
+        let config = AugmentationConfig {
+            cutout: Some(CutoutConfig {
+                size: 5,
+                probability: 1.0,
+            }),
+            ..Default::default()
+        };
+        let ones = Tensor::<B, 4>::ones([16, 2, 12, 12], &Default::default());
+        let out = twice(config, ones);
+
+        assert!(out.iter().all(|&v| v == 0.0 || v == 1.0), "mask is not binary");
+        for image in out.chunks(2 * 12 * 12) {
+            // チャンネルで同じ位置を消す (中心は画像内なので 3x3 から 5x5)
+            let (first, second) = image.split_at(12 * 12);
+            assert_eq!(first, second);
+            let erased = first.iter().filter(|&&v| v == 0.0).count();
+            assert!((9..=25).contains(&erased), "erased {erased}");
+        }
+        assert!(out.chunks(2 * 12 * 12).any(|image| image.iter().filter(|&&v| v == 0.0).count() == 50));
+    }
+}
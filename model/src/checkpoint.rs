@@ -0,0 +1,391 @@
+// src/checkpoint.rs
+use crate::config::{CheckpointConfig, DatasetConfig};
+use anyhow::{Result, anyhow};
+use burn::{
+    module::{AutodiffModule, Module},
+    optim::Optimizer,
+    record::{FileRecorder, FullPrecisionSettings, NamedMpkFileRecorder, Recorder},
+    tensor::backend::{AutodiffBackend, Backend},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const MODEL_FILE: &str = "model";
+const OPTIM_FILE: &str = "optim";
+// 保存時点で最良のエポックの重み (再開時に model.burn / model.bin をこれで書き直す)
+const BEST_FILE: &str = "best";
+// state.json は最後に書き込むので、存在すればチェックポイントは完全
+const STATE_FILE: &str = "state.json";
+
+// 再開後に同じ結果になるよう、CompactRecorder (半精度) ではなく全精度で保存する
+type CheckpointRecorder = NamedMpkFileRecorder<FullPrecisionSettings>;
+
+/// 学習の進行状況 (チェックポイントの state.json)
+///
+/// 乱数状態は `seed` と進行カウンタから再現する:
+/// シャッフル順はデータローダーを `epoch - 1` 回進めることで、
+/// バックエンドの乱数 (Dropout 等) は保存時点で `rng_seed()` により再シードすることで復元する。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrainState {
+    /// 実行中 (再開時は次に実行する) エポック (1始まり)
+    pub epoch: u32,
+    /// 現在のエポック内で完了したステップ数
+    pub step_in_epoch: usize,
+    pub global_step: usize,
+    /// 現在のエポックの損失合計
+    pub running_loss: f32,
+    pub seed: u64,
+    /// 直前に終了したエポックの検証スコア (`Monitor::score`, エポック途中の保存では None)
+    #[serde(default)]
+    pub val_score: Option<f32>,
+    /// これまでで最良の検証スコアとそのエポック
+    #[serde(default)]
+    pub best_score: Option<f32>,
+    #[serde(default)]
+    pub best_epoch: u32,
+    /// 最良スコアが更新されなかった連続エポック数
+    #[serde(default)]
+    pub epochs_since_best: usize,
+    /// 学習開始からの経過時間 (再開前の実行分を含む)
+    #[serde(default)]
+    pub elapsed_secs: f64,
+}
+
+impl TrainState {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            epoch: 1,
+            step_in_epoch: 0,
+            global_step: 0,
+            running_loss: 0.0,
+            seed,
+            val_score: None,
+            best_score: None,
+            best_epoch: 0,
+            epochs_since_best: 0,
+            elapsed_secs: 0.0,
+        }
+    }
+
+    /// 1ステップ分の進行を記録
+    pub fn step(&mut self, loss: f32) {
+        self.running_loss += loss;
+        self.step_in_epoch += 1;
+        self.global_step += 1;
+        self.val_score = None;
+    }
+
+    pub fn train_loss(&self) -> f32 {
+        self.running_loss / (self.step_in_epoch.max(1) as f32)
+    }
+
+    /// エポック終了を記録し、次のエポックの先頭に進める
+    ///
+    /// 検証スコアが最良を `min_delta` より上回ったら true
+    pub fn finish_epoch(&mut self, val_score: f32, min_delta: f32) -> bool {
+        let improved = self
+            .best_score
+            .is_none_or(|best| val_score > best + min_delta);
+        if improved {
+            self.best_score = Some(val_score);
+            self.best_epoch = self.epoch;
+            self.epochs_since_best = 0;
+        } else {
+            self.epochs_since_best += 1;
+        }
+
+        self.epoch += 1;
+        self.step_in_epoch = 0;
+        self.running_loss = 0.0;
+        self.val_score = Some(val_score);
+        improved
+    }
+
+    /// 早期終了の条件を満たしたか
+    pub fn should_stop(&self, patience: Option<usize>) -> bool {
+        patience.is_some_and(|p| self.epochs_since_best >= p.max(1))
+    }
+
+    /// この時点から先で使うバックエンドの乱数シード
+    pub fn rng_seed(&self) -> u64 {
+        self.seed.wrapping_add(self.global_step as u64)
+    }
+}
+
+/// `artifacts.dir/checkpoints/step-XXXXXXXX/` にモデル・オプティマイザ・進行状況を保存する
+pub struct Checkpointer {
+    dir: PathBuf,
+    config: CheckpointConfig,
+}
+
+impl Checkpointer {
+    pub fn new(config: &DatasetConfig) -> Self {
+        Self {
+            dir: PathBuf::from(config.get_checkpoint_dir()),
+            config: config.training.checkpoint.clone(),
+        }
+    }
+
+    /// エポック途中で保存するステップか
+    pub fn should_save_step(&self, global_step: usize) -> bool {
+        match self.config.every_steps {
+            Some(n) if n > 0 => global_step.is_multiple_of(n),
+            _ => false,
+        }
+    }
+
+    /// チェックポイントを保存し、保持ポリシーを適用
+    ///
+    /// `best` はこれまでで最良のエポックの重み (まだなければ None)。
+    pub fn save<B, M, O>(
+        &self,
+        model: &M,
+        best: Option<&M::InnerModule>,
+        optim: &O,
+        state: &TrainState,
+    ) -> Result<PathBuf>
+    where
+        B: AutodiffBackend,
+        M: AutodiffModule<B>,
+        O: Optimizer<M, B>,
+    {
+        let path = self.dir.join(format!("step-{:08}", state.global_step));
+        fs::create_dir_all(&path)?;
+
+        let recorder = CheckpointRecorder::new();
+        model
+            .clone()
+            .save_file(path.join(MODEL_FILE), &recorder)
+            .map_err(|e| anyhow!("チェックポイント(モデル)の保存失敗: {e}"))?;
+        Recorder::<B>::record(&recorder, optim.to_record(), path.join(OPTIM_FILE))
+            .map_err(|e| anyhow!("チェックポイント(オプティマイザ)の保存失敗: {e}"))?;
+        if let Some(best) = best {
+            best.clone()
+                .save_file(path.join(BEST_FILE), &recorder)
+                .map_err(|e| anyhow!("チェックポイント(最良モデル)の保存失敗: {e}"))?;
+        }
+        fs::write(path.join(STATE_FILE), serde_json::to_string_pretty(state)?)?;
+
+        self.apply_retention()?;
+        Ok(path)
+    }
+
+    /// `--resume` の引数からチェックポイントのパスを解決 ("latest" なら最新)
+    pub fn resolve(&self, resume: &str) -> Result<PathBuf> {
+        if resume != "latest" {
+            let path = PathBuf::from(resume);
+            if !path.join(STATE_FILE).is_file() {
+                return Err(anyhow!("チェックポイントが見つかりません: {}", path.display()));
+            }
+            return Ok(path);
+        }
+
+        self.list()?
+            .pop()
+            .map(|(path, _)| path)
+            .ok_or_else(|| anyhow!("チェックポイントがありません: {}", self.dir.display()))
+    }
+
+    /// チェックポイントからモデル・オプティマイザ・進行状況を復元
+    pub fn load<B, M, O>(
+        &self,
+        path: &Path,
+        model: M,
+        optim: O,
+        device: &B::Device,
+    ) -> Result<(M, O, TrainState)>
+    where
+        B: AutodiffBackend,
+        M: AutodiffModule<B>,
+        O: Optimizer<M, B>,
+    {
+        let state = read_state(path)?;
+
+        let recorder = CheckpointRecorder::new();
+        let model = model
+            .load_file(path.join(MODEL_FILE), &recorder, device)
+            .map_err(|e| anyhow!("チェックポイント(モデル)の読み込み失敗: {e}"))?;
+        let record = Recorder::<B>::load::<O::Record>(&recorder, path.join(OPTIM_FILE), device)
+            .map_err(|e| anyhow!("チェックポイント(オプティマイザ)の読み込み失敗: {e}"))?;
+        let optim = optim.load_record(record);
+
+        Ok((model, optim, state))
+    }
+
+    /// チェックポイントに保存した最良のエポックの重みを `model` に読み込む
+    /// (最良のエポックがまだない、または最良モデルを保存していない古いチェックポイントなら None)
+    pub fn load_best<B: Backend, M: Module<B>>(&self, path: &Path, model: M, device: &B::Device) -> Result<Option<M>> {
+        let recorder = CheckpointRecorder::new();
+        let best_path = path.join(BEST_FILE);
+        if !best_path.with_extension(<CheckpointRecorder as FileRecorder<B>>::file_extension()).is_file() {
+            return Ok(None);
+        }
+        let model = model
+            .load_file(best_path, &recorder, device)
+            .map_err(|e| anyhow!("チェックポイント(最良モデル)の読み込み失敗: {e}"))?;
+        Ok(Some(model))
+    }
+
+    /// 再開点より先のチェックポイント (放棄した実行の続き) を削除し、削除したパスを返す
+    ///
+    /// 残しておくと保持ポリシーが新しいチェックポイントの方を消し、
+    /// `--resume` (latest) も放棄した実行を指してしまう。
+    pub fn discard_after(&self, global_step: usize) -> Result<Vec<PathBuf>> {
+        let mut discarded = Vec::new();
+        for (path, state) in self.list()? {
+            if state.global_step > global_step {
+                fs::remove_dir_all(&path)?;
+                discarded.push(path);
+            }
+        }
+        Ok(discarded)
+    }
+
+    /// 完全なチェックポイントを global_step 昇順で列挙
+    fn list(&self) -> Result<Vec<(PathBuf, TrainState)>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut checkpoints: Vec<_> = fs::read_dir(&self.dir)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with("step-"))
+            })
+            .filter_map(|p| read_state(&p).ok().map(|state| (p, state)))
+            .collect();
+        checkpoints.sort_by_key(|(_, state)| state.global_step);
+        Ok(checkpoints)
+    }
+
+    fn apply_retention(&self) -> Result<()> {
+        let Some(keep_last) = self.config.keep_last else {
+            return Ok(());
+        };
+
+        let checkpoints = self.list()?;
+        let best = if self.config.keep_best {
+            checkpoints
+                .iter()
+                .filter_map(|(path, state)| state.val_score.map(|score| (path, score)))
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .map(|(path, _)| path.clone())
+        } else {
+            None
+        };
+
+        let stale = checkpoints.len().saturating_sub(keep_last.max(1));
+        for (path, _) in checkpoints.iter().take(stale) {
+            if best.as_ref() != Some(path) {
+                fs::remove_dir_all(path)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_state(path: &Path) -> Result<TrainState> {
+    let state_path = path.join(STATE_FILE);
+    let json = fs::read_to_string(&state_path)
+        .map_err(|e| anyhow!("チェックポイント読み込み失敗 {}: {}", state_path.display(), e))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// state.json だけの (列挙・保持ポリシーの対象になる) チェックポイントを作る
+    fn write_checkpoint(dir: &Path, state: &TrainState) {
+        let path = dir.join(format!("step-{:08}", state.global_step));
+        fs::create_dir_all(&path).unwrap();
+        fs::write(path.join(STATE_FILE), serde_json::to_string(state).unwrap()).unwrap();
+    }
+
+    fn state(global_step: usize, running_loss: f32) -> TrainState {
+        TrainState {
+            global_step,
+            running_loss,
+            ..TrainState::new(0)
+        }
+    }
+
+    #[test]
+    fn finish_epoch_tracks_best_and_patience() {
+        let mut state = TrainState::new(0);
+        state.step(2.0);
+        state.step(1.0);
+        assert_eq!(state.train_loss(), 1.5);
+
+        // 最初のエポックは必ず最良
+        assert!(state.finish_epoch(0.50, 0.01));
+        assert_eq!((state.epoch, state.step_in_epoch, state.running_loss), (2, 0, 0.0));
+        assert_eq!((state.best_epoch, state.best_score, state.val_score), (1, Some(0.50), Some(0.50)));
+
+        // min_delta 以下の改善は改善とみなさない
+        assert!(!state.finish_epoch(0.505, 0.01));
+        assert_eq!((state.best_epoch, state.epochs_since_best), (1, 1));
+        assert!(!state.should_stop(Some(2)));
+        assert!(state.finish_epoch(0.60, 0.01));
+        assert_eq!((state.best_epoch, state.best_score, state.epochs_since_best), (3, Some(0.60), 0));
+
+        assert!(!state.finish_epoch(0.55, 0.0));
+        assert!(!state.should_stop(Some(2)));
+        assert!(!state.finish_epoch(0.60, 0.0));
+        assert_eq!(state.epochs_since_best, 2);
+        assert!(state.should_stop(Some(2)));
+        assert!(!state.should_stop(None));
+        // patience 0 は 1 として扱う
+        assert!(state.should_stop(Some(0)));
+        assert_eq!(state.best_epoch, 3);
+
+        // エポック途中のステップで val_score は消える (保持ポリシーの最良判定に使わない)
+        state.step(1.0);
+        assert_eq!(state.val_score, None);
+    }
+
+    #[test]
+    fn resume_from_older_checkpoint_discards_abandoned_run() {
+        let dir = std::env::temp_dir().join(format!("checkpoint-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let checkpointer = Checkpointer {
+            dir: dir.clone(),
+            config: CheckpointConfig {
+                every_steps: None,
+                keep_last: Some(2),
+                keep_best: false,
+            },
+        };
+        for step in [10, 20, 30, 40] {
+            write_checkpoint(&dir, &state(step, 0.0));
+        }
+
+        // step 20 から再開すると 30, 40 は放棄した実行の続き
+        let resumed = checkpointer.resolve(dir.join("step-00000020").to_str().unwrap()).unwrap();
+        assert_eq!(read_state(&resumed).unwrap().global_step, 20);
+        assert_eq!(checkpointer.discard_after(20).unwrap().len(), 2);
+
+        // 再開後の保存が保持ポリシーで消されず、latest も新しい実行を指す
+        for step in [25, 30] {
+            write_checkpoint(&dir, &state(step, 1.0));
+            checkpointer.apply_retention().unwrap();
+        }
+        let kept: Vec<(usize, f32)> = checkpointer
+            .list()
+            .unwrap()
+            .into_iter()
+            .map(|(_, state)| (state.global_step, state.running_loss))
+            .collect();
+        assert_eq!(kept, [(25, 1.0), (30, 1.0)]);
+        let latest = read_state(&checkpointer.resolve("latest").unwrap()).unwrap();
+        assert_eq!((latest.global_step, latest.running_loss), (30, 1.0));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
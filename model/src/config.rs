@@ -1,7 +1,11 @@
 use anyhow::{Result, anyhow};
 use serde::{Deserialize, Serialize};
+use std::fmt;
 use std::fs;
 
+/// 学習ログのファイル名 (`artifacts.dir` 直下)
+pub const METRICS_FILE: &str = "metrics.jsonl";
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct ModelConfig {
     #[serde(rename = "type")]
@@ -9,8 +13,78 @@ pub struct ModelConfig {
     pub conv1_out: Option<usize>,
     pub conv2_out: Option<usize>,
     pub conv3_out: Option<usize>,
-    pub fc1_out: usize,
+    pub fc1_out: Option<usize>,
     pub fc2_out: Option<usize>,
+    /// resnet の層数 (6n+2, 既定 20)
+    pub depth: Option<usize>,
+    /// resnet の最初のステージのチャンネル数 (既定 16, ステージごとに2倍)
+    pub base_width: Option<usize>,
+    /// sequential の層 (先頭から順に適用する)
+    #[serde(default)]
+    pub layers: Vec<LayerConfig>,
+}
+
+/// `model.type = "sequential"` の1層
+///
+/// 形は `input_channels`/`input_size` から順に推論し、最後の出力が `[num_classes]` になるか確認する。
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LayerConfig {
+    /// 2D 畳み込み (`padding` 省略時は kernel / 2 で、stride 1 なら大きさが変わらない)
+    Conv {
+        out: usize,
+        kernel: usize,
+        #[serde(default = "default_stride")]
+        stride: usize,
+        padding: Option<usize>,
+        #[serde(default = "default_true")]
+        bias: bool,
+    },
+    /// `stride` 省略時は kernel と同じ
+    MaxPool { kernel: usize, stride: Option<usize> },
+    AvgPool { kernel: usize, stride: Option<usize> },
+    /// 画像ならチャンネルごと、flatten 後なら特徴ごとに正規化する
+    BatchNorm,
+    Dropout { prob: f64 },
+    /// `out` 省略時は `num_classes`
+    Linear { out: Option<usize> },
+    Activation { function: ActivationKind },
+    /// [C, H, W] → [C*H*W]
+    Flatten,
+}
+
+impl LayerConfig {
+    /// エラーメッセージ・表示用の名前 (`type` の値)
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LayerConfig::Conv { .. } => "conv",
+            LayerConfig::MaxPool { .. } => "max_pool",
+            LayerConfig::AvgPool { .. } => "avg_pool",
+            LayerConfig::BatchNorm => "batch_norm",
+            LayerConfig::Dropout { .. } => "dropout",
+            LayerConfig::Linear { .. } => "linear",
+            LayerConfig::Activation { .. } => "activation",
+            LayerConfig::Flatten => "flatten",
+        }
+    }
+}
+
+fn default_stride() -> usize {
+    1
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivationKind {
+    Relu,
+    LeakyRelu,
+    Gelu,
+    Tanh,
+    Sigmoid,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -26,12 +100,439 @@ pub enum NormalizationValue {
     Triple([f32; 3]),
 }
 
+impl NormalizationValue {
+    /// チャンネル数ぶんの値に展開 (単一値は全チャンネル共通)
+    pub fn per_channel(&self, channels: usize) -> Result<Vec<f32>> {
+        match self {
+            NormalizationValue::Single(v) => Ok(vec![*v; channels]),
+            NormalizationValue::Triple(v) if channels == 3 => Ok(v.to_vec()),
+            NormalizationValue::Triple(_) => Err(anyhow!(
+                "正規化の値が3つありますが、入力は{}チャンネルです",
+                channels
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct TrainingConfig {
     pub epochs: u32,
     pub batch_size: usize,
+    /// 基準の学習率 (スケジューラはこの値を基準に増減する)
     pub learning_rate: f64,
+    #[serde(default)]
+    pub optimizer: OptimizerConfig,
+    #[serde(default)]
+    pub lr_scheduler: LrSchedulerConfig,
     pub normalization: NormalizationConfig,
+    #[serde(default)]
+    pub checkpoint: CheckpointConfig,
+    #[serde(default)]
+    pub augmentation: AugmentationConfig,
+    #[serde(default)]
+    pub validation: ValidationConfig,
+    #[serde(default)]
+    pub early_stopping: EarlyStoppingConfig,
+}
+
+/// 学習データから切り出す検証用データ (毎エポックの評価に使う)
+///
+/// `image_folder` 以外。`image_folder` は `source.split` で分割する。
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ValidationConfig {
+    /// 検証用にする割合 (0 < fraction < 1)
+    pub fraction: f64,
+    /// 分割のシャッフルに使うシード
+    pub seed: u64,
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self {
+            fraction: 0.1,
+            seed: 42,
+        }
+    }
+}
+
+/// 最良モデルの選択と早期終了
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct EarlyStoppingConfig {
+    /// 最良モデルの判定に使う指標
+    pub monitor: Monitor,
+    /// この回数のエポックで改善がなければ終了 (null なら早期終了しない)
+    pub patience: Option<usize>,
+    /// これより小さい変化は改善とみなさない
+    pub min_delta: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Monitor {
+    #[default]
+    ValAcc,
+    ValLoss,
+}
+
+impl Monitor {
+    /// 大きいほど良いスコアに変換
+    pub fn score(self, val_acc: f32, val_loss: f32) -> f32 {
+        match self {
+            Monitor::ValAcc => val_acc,
+            Monitor::ValLoss => -val_loss,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OptimizerConfig {
+    Adam {
+        /// L2 正則化 (勾配に加算)
+        #[serde(default)]
+        weight_decay: Option<f32>,
+    },
+    /// 重み減衰を勾配と切り離した Adam
+    #[serde(rename = "adamw")]
+    AdamW {
+        #[serde(default = "default_adamw_weight_decay")]
+        weight_decay: f32,
+    },
+    Sgd {
+        /// 0 ならモーメンタムなし
+        #[serde(default = "default_sgd_momentum")]
+        momentum: f64,
+        #[serde(default)]
+        dampening: f64,
+        #[serde(default)]
+        nesterov: bool,
+        #[serde(default)]
+        weight_decay: Option<f32>,
+    },
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        OptimizerConfig::Adam { weight_decay: None }
+    }
+}
+
+impl OptimizerConfig {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            OptimizerConfig::Adam { .. } => "adam",
+            OptimizerConfig::AdamW { .. } => "adamw",
+            OptimizerConfig::Sgd { .. } => "sgd",
+        }
+    }
+}
+
+fn default_adamw_weight_decay() -> f32 {
+    1e-4
+}
+
+fn default_sgd_momentum() -> f64 {
+    0.9
+}
+
+/// 学習率スケジュール (エポック単位の設定を、ステップごとに評価する)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct LrSchedulerConfig {
+    #[serde(flatten)]
+    pub schedule: LrSchedule,
+    /// 最初の N エポック (小数可) で学習率を 0 付近から線形に上げる
+    #[serde(default)]
+    pub warmup_epochs: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LrSchedule {
+    #[default]
+    Constant,
+    /// `step_size` エポックごとに学習率を `gamma` 倍する
+    Step {
+        step_size: u32,
+        #[serde(default = "default_step_gamma")]
+        gamma: f64,
+    },
+    /// ウォームアップ後、学習終了までに `min_lr` へコサインで下げる
+    Cosine {
+        #[serde(default)]
+        min_lr: f64,
+    },
+    /// 1cycle: `learning_rate / div_factor` から `learning_rate` まで上げ、
+    /// `learning_rate / (div_factor * final_div_factor)` まで下げる
+    OneCycle {
+        #[serde(default = "default_one_cycle_pct_start")]
+        pct_start: f64,
+        #[serde(default = "default_one_cycle_div_factor")]
+        div_factor: f64,
+        #[serde(default = "default_one_cycle_final_div_factor")]
+        final_div_factor: f64,
+    },
+}
+
+impl LrSchedule {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LrSchedule::Constant => "constant",
+            LrSchedule::Step { .. } => "step",
+            LrSchedule::Cosine { .. } => "cosine",
+            LrSchedule::OneCycle { .. } => "one_cycle",
+        }
+    }
+}
+
+fn default_step_gamma() -> f64 {
+    0.1
+}
+
+fn default_one_cycle_pct_start() -> f64 {
+    0.3
+}
+
+fn default_one_cycle_div_factor() -> f64 {
+    25.0
+}
+
+fn default_one_cycle_final_div_factor() -> f64 {
+    1e4
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct CheckpointConfig {
+    /// エポック終了時に加えて N ステップごとにも保存する
+    pub every_steps: Option<usize>,
+    /// 直近 K 個のチェックポイントのみ残す (null なら全て残す)
+    pub keep_last: Option<usize>,
+    /// 検証スコアが最良のチェックポイントは keep_last に関係なく残す
+    pub keep_best: bool,
+}
+
+impl Default for CheckpointConfig {
+    fn default() -> Self {
+        Self {
+            every_steps: None,
+            keep_last: Some(3),
+            keep_best: true,
+        }
+    }
+}
+
+/// 学習データのみに適用するデータ拡張 (既定では全て無効)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct AugmentationConfig {
+    /// 上下左右を N 画素ゼロパディングしてから元のサイズでランダムに切り出す
+    pub random_crop_padding: Option<usize>,
+    /// 確率 0.5 で左右反転
+    pub horizontal_flip: bool,
+    pub color_jitter: Option<ColorJitterConfig>,
+    /// Cutout (RandomErasing): 正方形の領域を 0 で塗りつぶす
+    pub cutout: Option<CutoutConfig>,
+}
+
+impl AugmentationConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.random_crop_padding.is_some_and(|p| p > 0)
+            || self.horizontal_flip
+            || self.color_jitter.is_some()
+            || self.cutout.is_some()
+    }
+}
+
+/// 画像ごとに [1 - x, 1 + x] の係数をランダムに選んで適用する
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct ColorJitterConfig {
+    pub brightness: f32,
+    pub contrast: f32,
+    pub saturation: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct CutoutConfig {
+    /// 塗りつぶす正方形の一辺 (画素)
+    pub size: usize,
+    /// 画像ごとに適用する確率
+    pub probability: f64,
+}
+
+impl Default for CutoutConfig {
+    fn default() -> Self {
+        Self {
+            size: 8,
+            probability: 0.5,
+        }
+    }
+}
+
+/// 画像ファイルを入力サイズに合わせる方法 (学習・CLI推論・評価で共通)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct PreprocessingConfig {
+    pub filter: ResizeFilter,
+    pub resize: ResizeMode,
+    /// letterbox・center_crop の余白の色 (RGB, グレースケールでは輝度に変換)
+    pub pad_color: [u8; 3],
+    pub alpha: AlphaMode,
+    /// `alpha: "composite"` で合成する背景色 (RGB)
+    pub alpha_background: [u8; 3],
+}
+
+impl Default for PreprocessingConfig {
+    fn default() -> Self {
+        Self {
+            filter: ResizeFilter::Nearest,
+            resize: ResizeMode::Stretch,
+            pad_color: [0, 0, 0],
+            alpha: AlphaMode::Ignore,
+            alpha_background: [255, 255, 255],
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeFilter {
+    #[default]
+    Nearest,
+    Bilinear,
+    Lanczos,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ResizeMode {
+    /// 縦横比を無視して入力サイズに引き伸ばす
+    #[default]
+    Stretch,
+    /// 縦横比を保って全体が収まるように縮小し、余白を `pad_color` で埋める
+    Letterbox,
+    /// リサイズせずに中央を切り出す (足りない部分は `pad_color`)
+    CenterCrop,
+    /// 縦横比を保って短辺を入力サイズに合わせ、はみ出した部分を中央で切り落とす
+    ShorterSideCrop,
+    /// 手書き数字の写真・スキャンを MNIST と同じ形にする (グレースケールのみ):
+    /// 白地に黒なら反転し、二値化の閾値で背景を消し、数字を切り出して長辺 20px (28px 中) に縮小、
+    /// 重心が中央に来るよう配置する。wasm の入力 (28x28) にも適用する
+    MnistCanonicalize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AlphaMode {
+    /// アルファチャンネルを捨てる (透明部分は元の色のまま)
+    #[default]
+    Ignore,
+    /// `alpha_background` の上に合成する
+    Composite,
+}
+
+/// 学習・評価に使うデータ
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DatasetSource {
+    /// `name` で選ぶ組み込みデータセット (mnist / cifar10)
+    ///
+    /// mnist は `datasets/mnist/` に IDX ファイルがあればそれを、なければ burn のダウンローダーを使う。
+    /// cifar10 は `{"type": "cifar10"}` の既定値と同じ。
+    #[default]
+    Builtin,
+    /// CIFAR-10 バイナリ (`dir/data_batch_{1..5}.bin`, `dir/test_batch.bin`)
+    Cifar10 {
+        #[serde(default = "default_cifar10_dir")]
+        dir: String,
+        /// ファイルを読まずに合成データ (ノイズとランダムなラベル) を使う (動作確認用)
+        #[serde(default)]
+        synthetic: bool,
+    },
+    /// `dir` の IDX ファイル (`train-images-idx3-ubyte[.gz]`, `t10k-labels-idx1-ubyte[.gz]` など) の
+    /// 28x28 グレースケール画像 (Fashion-MNIST / KMNIST / EMNIST)
+    Idx {
+        dir: String,
+        /// 画像が転置 (列優先) で保存されている (EMNIST)
+        #[serde(default)]
+        transpose: bool,
+        /// ラベルから引く値 (EMNIST letters はラベルが 1 始まりなので 1)
+        #[serde(default)]
+        label_offset: u8,
+    },
+    /// CIFAR-100 バイナリ (`dir/train.bin`, `dir/test.bin`)
+    ///
+    /// `class_names` を省略すると `dir/fine_label_names.txt` (または coarse) から読み込む。
+    Cifar100 {
+        #[serde(default = "default_cifar100_dir")]
+        dir: String,
+        #[serde(default)]
+        label: Cifar100Label,
+    },
+    /// `root/<クラス名>/` 以下の画像ファイル (PNG/JPEG/BMP/GIF/WebP/TIFF, サブディレクトリも含む)
+    ImageFolder {
+        root: String,
+        #[serde(default)]
+        split: SplitConfig,
+    },
+}
+
+fn default_cifar10_dir() -> String {
+    "datasets/cifar-10/cifar-10-batches-bin".to_string()
+}
+
+fn default_cifar100_dir() -> String {
+    "datasets/cifar-100/cifar-100-binary".to_string()
+}
+
+/// CIFAR-100 の2種類のラベル
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Cifar100Label {
+    /// 100クラス
+    #[default]
+    Fine,
+    /// 20 のスーパークラス
+    Coarse,
+}
+
+impl Cifar100Label {
+    pub fn kind(self) -> &'static str {
+        match self {
+            Cifar100Label::Fine => "fine",
+            Cifar100Label::Coarse => "coarse",
+        }
+    }
+
+    /// クラス名の一覧ファイル
+    pub fn names_path(self, dir: &str) -> String {
+        format!("{}/{}_label_names.txt", dir, self.kind())
+    }
+}
+
+/// 画像フォルダを学習・検証・テストに分ける割合 (クラスごとに分割する)
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SplitConfig {
+    pub train: f64,
+    pub val: f64,
+    pub test: f64,
+    /// 分割のシャッフルに使うシード
+    pub seed: u64,
+}
+
+impl Default for SplitConfig {
+    fn default() -> Self {
+        Self {
+            train: 0.8,
+            val: 0.1,
+            test: 0.1,
+            seed: 42,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -46,10 +547,18 @@ pub struct ArtifactsConfig {
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct DatasetConfig {
     pub name: String,
+    #[serde(default)]
+    pub source: DatasetSource,
     pub input_channels: usize,
     pub input_size: [usize; 2],
+    /// `image_folder`・`cifar100` では省略可 (0 ならクラス名の数)
+    #[serde(default)]
     pub num_classes: usize,
+    /// `image_folder` (空ならフォルダ名の順)・`cifar100` (空ならラベル名ファイル) では省略可
+    #[serde(default)]
     pub class_names: Vec<String>,
+    #[serde(default)]
+    pub preprocessing: PreprocessingConfig,
     pub model: ModelConfig,
     pub training: TrainingConfig,
     pub artifacts: ArtifactsConfig,
@@ -61,12 +570,204 @@ impl DatasetConfig {
         let config_str = fs::read_to_string(&config_path)
             .map_err(|e| anyhow!("設定ファイル読み込み失敗 {}: {}", config_path, e))?;
 
-        let config: DatasetConfig = serde_json::from_str(&config_str)
+        let mut config: DatasetConfig = serde_json::from_str(&config_str)
             .map_err(|e| anyhow!("設定ファイル解析失敗 {}: {}", config_path, e))?;
+        if matches!(config.source, DatasetSource::Builtin) && config.name == "cifar10" {
+            config.source = DatasetSource::Cifar10 {
+                dir: default_cifar10_dir(),
+                synthetic: false,
+            };
+        }
+        config
+            .resolve_classes()
+            .map_err(|e| anyhow!("{}: {}", config_path, e))?;
+        config
+            .validate()
+            .map_err(|e| anyhow!("{}: {}", config_path, e))?;
 
         Ok(config)
     }
 
+    /// `configs/` にある設定の名前 (`load` に渡せるもの) をすべて返す
+    pub fn available() -> Result<Vec<String>> {
+        let entries = fs::read_dir("configs").map_err(|e| anyhow!("configs を開けません: {}", e))?;
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    /// 項目どうしの矛盾を確認する (`load` が最後に呼ぶ)
+    ///
+    /// 学習・推論の途中で失敗する前に、どの項目を直せばよいかを返す。
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.input_channels == 0 || self.input_size.contains(&0) {
+            return Err(ConfigError::EmptyInput {
+                channels: self.input_channels,
+                size: self.input_size,
+            });
+        }
+        if self.num_classes == 0 {
+            return Err(ConfigError::NoClasses);
+        }
+        if self.class_names.len() != self.num_classes {
+            return Err(ConfigError::ClassCount {
+                num_classes: self.num_classes,
+                class_names: self.class_names.len(),
+            });
+        }
+
+        let normalization = &self.training.normalization;
+        for (field, value) in [("mean", &normalization.mean), ("std", &normalization.std)] {
+            match value {
+                NormalizationValue::Triple(_) if self.input_channels != 3 => {
+                    return Err(ConfigError::NormalizationChannels {
+                        field,
+                        channels: self.input_channels,
+                    });
+                }
+                // カラー画像の統計量はチャンネルごとに違うので、1つの値は書き忘れとみなす
+                NormalizationValue::Single(_) if self.input_channels == 3 => {
+                    return Err(ConfigError::ScalarNormalization { field });
+                }
+                _ => {}
+            }
+        }
+        let std = match &normalization.std {
+            NormalizationValue::Single(v) => vec![*v],
+            NormalizationValue::Triple(v) => v.to_vec(),
+        };
+        if std.iter().any(|v| !(*v > 0.0 && v.is_finite())) {
+            return Err(ConfigError::NonPositiveStd(std));
+        }
+
+        if self.preprocessing.resize == ResizeMode::MnistCanonicalize && self.input_channels != 1 {
+            return Err(ConfigError::GrayscaleOnly {
+                channels: self.input_channels,
+            });
+        }
+
+        crate::registry::check_model(self)
+    }
+
+    /// 実データの代わりに合成データを使う (`--synthetic`, CIFAR-10 のみ)
+    pub fn use_synthetic(&mut self) -> Result<()> {
+        match &mut self.source {
+            DatasetSource::Cifar10 { synthetic, .. } => {
+                *synthetic = true;
+                Ok(())
+            }
+            _ => Err(anyhow!("合成データは CIFAR-10 のみ対応しています: {}", self.name)),
+        }
+    }
+
+    /// クラス名を `image_folder` はフォルダ名、`cifar100` はラベル名ファイルから決める
+    /// (設定にあれば一致するか確認)
+    ///
+    /// クラス名が設定済みでデータがない場合 (データなしで推論する場合など) は確認しない。
+    fn resolve_classes(&mut self) -> Result<(), ConfigError> {
+        match self.source.clone() {
+            DatasetSource::ImageFolder { root, .. } => self.resolve_image_folder_classes(&root)?,
+            DatasetSource::Cifar100 { dir, label } => self.resolve_label_file_classes(&label.names_path(&dir))?,
+            DatasetSource::Builtin | DatasetSource::Cifar10 { .. } | DatasetSource::Idx { .. } => return Ok(()),
+        }
+
+        if self.num_classes == 0 {
+            self.num_classes = self.class_names.len();
+        } else if self.num_classes != self.class_names.len() {
+            return Err(ConfigError::ClassCount {
+                num_classes: self.num_classes,
+                class_names: self.class_names.len(),
+            });
+        }
+        Ok(())
+    }
+
+    fn resolve_image_folder_classes(&mut self, root: &str) -> Result<(), ConfigError> {
+        let folders = match fs::read_dir(root) {
+            Ok(entries) => {
+                let mut folders: Vec<String> = entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_ok_and(|t| t.is_dir()))
+                    .map(|e| e.file_name().to_string_lossy().into_owned())
+                    .filter(|name| !name.starts_with('.'))
+                    .collect();
+                folders.sort();
+                Some(folders)
+            }
+            Err(_) if !self.class_names.is_empty() => None,
+            Err(error) => {
+                return Err(ConfigError::ImageFolderUnreadable {
+                    root: root.to_string(),
+                    error,
+                });
+            }
+        };
+
+        if let Some(folders) = folders {
+            if self.class_names.is_empty() {
+                self.class_names = folders;
+            } else {
+                let missing: Vec<String> = self
+                    .class_names
+                    .iter()
+                    .filter(|c| !folders.contains(c))
+                    .cloned()
+                    .collect();
+                let extra: Vec<String> = folders
+                    .iter()
+                    .filter(|f| !self.class_names.contains(f))
+                    .cloned()
+                    .collect();
+                if !missing.is_empty() || !extra.is_empty() {
+                    return Err(ConfigError::ClassFolderMismatch {
+                        root: root.to_string(),
+                        missing,
+                        extra,
+                    });
+                }
+            }
+        }
+
+        if self.class_names.is_empty() {
+            return Err(ConfigError::NoClassFolders { root: root.to_string() });
+        }
+        Ok(())
+    }
+
+    /// 1行 = 1クラス (ラベル番号順) のファイル
+    fn resolve_label_file_classes(&mut self, path: &str) -> Result<(), ConfigError> {
+        let names: Vec<String> = match fs::read_to_string(path) {
+            Ok(text) => text
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+            Err(_) if !self.class_names.is_empty() => return Ok(()),
+            Err(error) => {
+                return Err(ConfigError::LabelFileUnreadable {
+                    path: path.to_string(),
+                    error,
+                });
+            }
+        };
+
+        if self.class_names.is_empty() {
+            self.class_names = names;
+        } else if self.class_names != names {
+            return Err(ConfigError::LabelFileMismatch { path: path.to_string() });
+        }
+        if self.class_names.is_empty() {
+            return Err(ConfigError::EmptyLabelFile { path: path.to_string() });
+        }
+        Ok(())
+    }
+
     pub fn get_model_path(&self) -> String {
         format!("{}/{}", self.artifacts.dir, self.artifacts.model_file)
     }
@@ -74,4 +775,234 @@ impl DatasetConfig {
     pub fn get_model_bin_path(&self) -> String {
         format!("{}/{}", self.artifacts.dir, self.artifacts.model_bin)
     }
+
+    pub fn get_checkpoint_dir(&self) -> String {
+        format!("{}/checkpoints", self.artifacts.dir)
+    }
+
+    pub fn get_eval_report_path(&self) -> String {
+        format!("{}/eval.json", self.artifacts.dir)
+    }
+
+    pub fn get_folder_eval_report_path(&self) -> String {
+        format!("{}/eval_folder.json", self.artifacts.dir)
+    }
+
+    pub fn get_metrics_path(&self) -> String {
+        format!("{}/{}", self.artifacts.dir, METRICS_FILE)
+    }
+}
+
+/// `DatasetConfig::load` で見つかる設定の誤り (クラス名の解決と `validate`)
+#[derive(Debug)]
+pub enum ConfigError {
+    /// `image_folder` の root を開けず、`class_names` もない
+    ImageFolderUnreadable { root: String, error: std::io::Error },
+    /// `class_names` と `image_folder` のクラスのフォルダが一致しない
+    ClassFolderMismatch { root: String, missing: Vec<String>, extra: Vec<String> },
+    /// `image_folder` の root にクラスのフォルダがない
+    NoClassFolders { root: String },
+    /// ラベル名ファイルを読めず、`class_names` もない
+    LabelFileUnreadable { path: String, error: std::io::Error },
+    /// `class_names` がラベル名ファイルと一致しない
+    LabelFileMismatch { path: String },
+    /// ラベル名ファイルが空
+    EmptyLabelFile { path: String },
+    /// `input_channels`・`input_size` に 0 がある
+    EmptyInput { channels: usize, size: [usize; 2] },
+    /// `num_classes` が 0 (クラス名もない)
+    NoClasses,
+    /// `num_classes` と `class_names` の数が違う
+    ClassCount { num_classes: usize, class_names: usize },
+    /// 正規化の値が3つあるが入力が3チャンネルではない (`field` は mean か std)
+    NormalizationChannels { field: &'static str, channels: usize },
+    /// 3チャンネル入力の正規化の値が1つしかない (`field` は mean か std)
+    ScalarNormalization { field: &'static str },
+    /// 正規化の std に 0 以下の値がある
+    NonPositiveStd(Vec<f32>),
+    /// `mnist_canonicalize` をカラー入力に使っている
+    GrayscaleOnly { channels: usize },
+    /// `model.type` が登録されていない
+    UnknownModelType(String),
+    /// モデルの構造が入力の大きさ・クラス数と合わない
+    Model { model_type: &'static str, reason: String },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ImageFolderUnreadable { root, error } => write!(
+                f,
+                "画像フォルダを開けません {}: {} (class_names を設定すればデータなしでも推論できます)",
+                root, error
+            ),
+            ConfigError::ClassFolderMismatch { root, missing, extra } => write!(
+                f,
+                "class_names と {} のフォルダが一致しません (フォルダなし: [{}], 設定にないフォルダ: [{}])",
+                root,
+                missing.join(", "),
+                extra.join(", ")
+            ),
+            ConfigError::NoClassFolders { root } => write!(f, "{} にクラスのフォルダがありません", root),
+            ConfigError::LabelFileUnreadable { path, error } => write!(
+                f,
+                "ラベル名ファイルを読み込めません {}: {} (class_names を設定すればデータなしでも推論できます)",
+                path, error
+            ),
+            ConfigError::LabelFileMismatch { path } => {
+                write!(f, "class_names が {} と一致しません (ラベル番号の順に並べてください)", path)
+            }
+            ConfigError::EmptyLabelFile { path } => write!(f, "{} にクラス名がありません", path),
+            ConfigError::EmptyInput { channels, size } => write!(
+                f,
+                "input_channels と input_size は 1 以上にしてください: input_channels = {}, input_size = {:?}",
+                channels, size
+            ),
+            ConfigError::NoClasses => write!(f, "num_classes が 0 です (num_classes か class_names を設定してください)"),
+            ConfigError::ClassCount {
+                num_classes,
+                class_names,
+            } => write!(
+                f,
+                "num_classes ({}) と class_names の数 ({}) が一致しません (class_names をラベル番号の順にすべて並べてください)",
+                num_classes, class_names
+            ),
+            ConfigError::NormalizationChannels { field, channels } => write!(
+                f,
+                "training.normalization.{} の値が3つありますが、input_channels は {} です (1つにしてください)",
+                field, channels
+            ),
+            ConfigError::ScalarNormalization { field } => write!(
+                f,
+                "training.normalization.{} の値が1つですが、input_channels は 3 です (チャンネルごとに [R, G, B] の3つを指定してください)",
+                field
+            ),
+            ConfigError::NonPositiveStd(std) => {
+                write!(f, "training.normalization.std は正の値にしてください: {:?}", std)
+            }
+            ConfigError::GrayscaleOnly { channels } => write!(
+                f,
+                "preprocessing.resize = mnist_canonicalize はグレースケール (input_channels = 1) のみ対応です: input_channels = {}",
+                channels
+            ),
+            ConfigError::UnknownModelType(model_type) => write!(
+                f,
+                "未対応のモデルタイプ: {} (対応: {})",
+                model_type,
+                crate::registry::MODEL_TYPES.join(", ")
+            ),
+            ConfigError::Model { model_type, reason } => write!(f, "{} の設定が合いません: {}", model_type, reason),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mnist() -> DatasetConfig {
+        serde_json::from_str(include_str!("../configs/mnist.json")).expect("config")
+    }
+
+    #[test]
+    fn validate_reports_cross_field_mistakes() {
+        assert!(mnist().validate().is_ok());
+
+        let mut config = mnist();
+        config.class_names.pop();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ClassCount {
+                num_classes: 10,
+                class_names: 9
+            })
+        ));
+
+        let mut config = mnist();
+        config.training.normalization.mean = NormalizationValue::Triple([0.5; 3]);
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::NormalizationChannels { field: "mean", channels: 1 })
+        ));
+
+        // カラー入力に1つの値を書くと全チャンネル共通になってしまう
+        let mut config: DatasetConfig = serde_json::from_str(include_str!("../configs/cifar10.json")).expect("config");
+        assert!(config.validate().is_ok());
+        config.training.normalization.std = NormalizationValue::Single(0.25);
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::ScalarNormalization { field: "std" })
+        ));
+
+        let mut config = mnist();
+        config.model.model_type = "vgg".to_string();
+        assert!(matches!(config.validate(), Err(ConfigError::UnknownModelType(_))));
+
+        let mut config = mnist();
+        config.input_size = [30, 30];
+        let err = config.validate().unwrap_err();
+        assert!(matches!(err, ConfigError::Model { model_type: "lenet", .. }));
+        assert!(err.to_string().contains("4 の倍数"), "{err}");
+    }
+
+    #[test]
+    fn resolve_classes_reports_typed_errors() {
+        let root = std::env::temp_dir().join(format!("config-classes-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        let image_folder = |names: &[&str]| {
+            let mut config = mnist();
+            config.source = DatasetSource::ImageFolder {
+                root: root.to_string_lossy().into_owned(),
+                split: SplitConfig::default(),
+            };
+            config.class_names = names.iter().map(|s| s.to_string()).collect();
+            config.num_classes = names.len();
+            config
+        };
+
+        let empty = image_folder(&[]).resolve_classes();
+        for class in ["cat", "dog"] {
+            fs::create_dir_all(root.join(class)).unwrap();
+        }
+        let mut found = image_folder(&[]);
+        found.resolve_classes().unwrap();
+        let mismatch = image_folder(&["cat", "bird"]).resolve_classes();
+        let mut count = image_folder(&[]);
+        count.num_classes = 3;
+        let count = count.resolve_classes();
+
+        let labels = root.join("labels.txt");
+        fs::write(&labels, "cat\ndog\n").unwrap();
+        let label_file = |names: &[&str]| {
+            let mut config = image_folder(names);
+            config.resolve_label_file_classes(&labels.to_string_lossy())
+        };
+        let label_ok = label_file(&["cat", "dog"]);
+        let label_mismatch = label_file(&["dog", "cat"]);
+        fs::remove_dir_all(&root).unwrap();
+        let unreadable = image_folder(&[]).resolve_classes();
+
+        assert!(matches!(empty, Err(ConfigError::NoClassFolders { .. })));
+        assert_eq!(found.class_names, ["cat", "dog"]);
+        assert_eq!(found.num_classes, 2);
+        match mismatch {
+            Err(ConfigError::ClassFolderMismatch { missing, extra, .. }) => {
+                assert_eq!((missing, extra), (vec!["bird".to_string()], vec!["dog".to_string()]));
+            }
+            other => panic!("{other:?}"),
+        }
+        assert!(matches!(
+            count,
+            Err(ConfigError::ClassCount {
+                num_classes: 3,
+                class_names: 2
+            })
+        ));
+        assert!(label_ok.is_ok());
+        assert!(matches!(label_mismatch, Err(ConfigError::LabelFileMismatch { .. })));
+        assert!(matches!(unreadable, Err(ConfigError::ImageFolderUnreadable { .. })));
+    }
 }
@@ -0,0 +1,35 @@
+// src/csv.rs
+// `infer --format csv` と `report --format csv` の行の組み立て (値は必要なときだけクォートする)
+
+/// 値をクォートして ',' でつないだ1行
+pub fn row<S: AsRef<str>>(fields: &[S]) -> String {
+    fields
+        .iter()
+        .map(|f| field(f.as_ref()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// カンマ・引用符・改行を含む値を CSV 用にクォート
+pub fn field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_only_when_needed() {
+        assert_eq!(field("digits/7.png"), "digits/7.png");
+        assert_eq!(field("a,b.png"), "\"a,b.png\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("line1\nline2"), "\"line1\nline2\"");
+        assert_eq!(field(""), "");
+        assert_eq!(row(&["runs/a,b", "", "0.5"]), "\"runs/a,b\",,0.5");
+    }
+}
@@ -1,20 +1,115 @@
-use burn::{data::dataloader::batcher::Batcher, data::dataset::vision::MnistItem, prelude::*};
+use crate::augment::Augmenter;
+use crate::config::{Cifar100Label, DatasetConfig, DatasetSource, ValidationConfig};
+use crate::idx::{self, IdxDataset};
+use crate::image_folder::ImageFolderItem;
+use crate::preprocess::Preprocessor;
+use anyhow::{Result, anyhow};
+use burn::{
+    data::dataloader::batcher::Batcher,
+    data::dataset::{
+        Dataset,
+        transform::{PartialDataset, ShuffledDataset},
+        vision::{MnistDataset, MnistItem},
+    },
+    prelude::*,
+};
+use std::fmt;
 use std::fs::File;
 use std::io::{BufReader, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-// CIFAR-10用のアイテム構造体を定義
+// CIFAR-10 / CIFAR-100 用のアイテム構造体を定義
 #[derive(Clone, Debug)]
-pub struct Cifar10Item {
+pub struct CifarItem {
     pub image: [[[f32; 32]; 32]; 3], // RGB channels, 32x32
     pub label: usize,
 }
 
-#[derive(Clone, Default)]
-pub struct MnistBatcher;
+#[derive(Clone, Debug)]
+pub struct MnistBatcher {
+    preprocessor: Preprocessor,
+    augmenter: Option<Augmenter>,
+}
+
+impl MnistBatcher {
+    pub fn new(config: &DatasetConfig) -> Result<Self> {
+        let preprocessor = Preprocessor::new(config)?;
+        preprocessor.expect_shape("MNIST", 1, 28, 28)?;
+        Ok(Self {
+            preprocessor,
+            augmenter: None,
+        })
+    }
 
-#[derive(Clone, Default)]
-pub struct CifarBatcher;
+    /// `training.augmentation` を適用する (学習用ローダーのみ)
+    pub fn with_augmentation(mut self, config: &DatasetConfig) -> Self {
+        self.augmenter = Augmenter::new(&config.training.augmentation);
+        self
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CifarBatcher {
+    preprocessor: Preprocessor,
+    augmenter: Option<Augmenter>,
+}
+
+impl CifarBatcher {
+    pub fn new(config: &DatasetConfig) -> Result<Self> {
+        let preprocessor = Preprocessor::new(config)?;
+        preprocessor.expect_shape("CIFAR", 3, 32, 32)?;
+        Ok(Self {
+            preprocessor,
+            augmenter: None,
+        })
+    }
+
+    /// `training.augmentation` を適用する (学習用ローダーのみ)
+    pub fn with_augmentation(mut self, config: &DatasetConfig) -> Self {
+        self.augmenter = Augmenter::new(&config.training.augmentation);
+        self
+    }
+}
+
+/// 画像フォルダ用 (画像は読み込み時に `preprocessing` で入力サイズに揃えてある)
+#[derive(Clone, Debug)]
+pub struct ImageFolderBatcher {
+    preprocessor: Preprocessor,
+    augmenter: Option<Augmenter>,
+}
+
+impl ImageFolderBatcher {
+    pub fn new(config: &DatasetConfig) -> Result<Self> {
+        Ok(Self {
+            preprocessor: Preprocessor::new(config)?,
+            augmenter: None,
+        })
+    }
+
+    /// `training.augmentation` を適用する (学習用ローダーのみ)
+    pub fn with_augmentation(mut self, config: &DatasetConfig) -> Self {
+        self.augmenter = Augmenter::new(&config.training.augmentation);
+        self
+    }
+}
+
+/// 拡張 (学習時のみ) → 正規化
+fn preprocess<B: Backend>(
+    preprocessor: &Preprocessor,
+    augmenter: Option<&Augmenter>,
+    pixels: Vec<f32>,
+    count: usize,
+    device: &B::Device,
+) -> Tensor<B, 4> {
+    match augmenter {
+        Some(augmenter) => {
+            let images = preprocessor.raw_batch(pixels, count, device);
+            preprocessor.normalize(augmenter.apply(images))
+        }
+        None => preprocessor.batch(pixels, count, device),
+    }
+}
 
 #[derive(Clone, Debug)]
 pub struct MnistBatch<B: Backend> {
@@ -28,15 +123,58 @@ pub struct CifarBatch<B: Backend> {
     pub targets: Tensor<B, 1, Int>,
 }
 
+#[derive(Clone, Debug)]
+pub struct ImageFolderBatch<B: Backend> {
+    pub images: Tensor<B, 4>, // [batch_size, channels, height, width]
+    pub targets: Tensor<B, 1, Int>,
+}
+
+/// 学習・評価ループが扱うバッチの共通インターフェース
+pub trait ImageBatch<B: Backend> {
+    /// [batch_size, channels, height, width]
+    fn images(&self) -> &Tensor<B, 4>;
+    /// [batch_size]
+    fn targets(&self) -> &Tensor<B, 1, Int>;
+}
+
+impl<B: Backend> ImageBatch<B> for MnistBatch<B> {
+    fn images(&self) -> &Tensor<B, 4> {
+        &self.images
+    }
+
+    fn targets(&self) -> &Tensor<B, 1, Int> {
+        &self.targets
+    }
+}
+
+impl<B: Backend> ImageBatch<B> for CifarBatch<B> {
+    fn images(&self) -> &Tensor<B, 4> {
+        &self.images
+    }
+
+    fn targets(&self) -> &Tensor<B, 1, Int> {
+        &self.targets
+    }
+}
+
+impl<B: Backend> ImageBatch<B> for ImageFolderBatch<B> {
+    fn images(&self) -> &Tensor<B, 4> {
+        &self.images
+    }
+
+    fn targets(&self) -> &Tensor<B, 1, Int> {
+        &self.targets
+    }
+}
+
 impl<B: Backend> Batcher<B, MnistItem, MnistBatch<B>> for MnistBatcher {
     fn batch(&self, items: Vec<MnistItem>, device: &B::Device) -> MnistBatch<B> {
-        let images = items
+        // [H,W] の画素を連結 (1チャンネルなので CHW と同じ並び)
+        let pixels: Vec<f32> = items
             .iter()
-            .map(|item| TensorData::from(item.image).convert::<B::FloatElem>())
-            .map(|data| Tensor::<B, 2>::from_data(data, device))
-            .map(|t| t.reshape([1, 28, 28])) // [C,H,W]
-            .map(|t| ((t / 255.0) - 0.1307) / 0.3081) // 正規化 (MNIST慣例)
+            .flat_map(|item| item.image.iter().flatten().copied())
             .collect();
+        let images = preprocess(&self.preprocessor, self.augmenter.as_ref(), pixels, items.len(), device); // [B,1,28,28]
 
         let targets = items
             .iter()
@@ -44,34 +182,38 @@ impl<B: Backend> Batcher<B, MnistItem, MnistBatch<B>> for MnistBatcher {
                 Tensor::<B, 1, Int>::from_data([(item.label as i64).elem::<B::IntElem>()], device)
             })
             .collect();
-
-        let images = Tensor::cat(images, 0).reshape([-1, 1, 28, 28]); // [B,1,28,28]
         let targets = Tensor::cat(targets, 0); // [B]
 
         MnistBatch { images, targets }
     }
 }
 
-impl<B: Backend> Batcher<B, Cifar10Item, CifarBatch<B>> for CifarBatcher {
-    fn batch(&self, items: Vec<Cifar10Item>, device: &B::Device) -> CifarBatch<B> {
-        let images = items
+impl<B: Backend> Batcher<B, CifarItem, CifarBatch<B>> for CifarBatcher {
+    fn batch(&self, items: Vec<CifarItem>, device: &B::Device) -> CifarBatch<B> {
+        // image は [3, 32, 32] (CHW)
+        let pixels: Vec<f32> = items
+            .iter()
+            .flat_map(|item| item.image.iter().flatten().flatten().copied())
+            .collect();
+        let images = preprocess(&self.preprocessor, self.augmenter.as_ref(), pixels, items.len(), device); // [B,3,32,32]
+
+        let targets = items
             .iter()
             .map(|item| {
-                // image is [3, 32, 32] - convert to tensor
-                let mut data = Vec::new();
-                for c in 0..3 {
-                    for h in 0..32 {
-                        for w in 0..32 {
-                            data.push(item.image[c][h][w]);
-                        }
-                    }
-                }
-                TensorData::from(data.as_slice()).convert::<B::FloatElem>()
+                Tensor::<B, 1, Int>::from_data([(item.label as i64).elem::<B::IntElem>()], device)
             })
-            .map(|data| Tensor::<B, 1>::from_data(data, device))
-            .map(|t| t.reshape([3, 32, 32])) // [C,H,W]
-            .map(|t| (t / 255.0)) // 正規化 [0,1]
             .collect();
+        let targets = Tensor::cat(targets, 0); // [B]
+
+        CifarBatch { images, targets }
+    }
+}
+
+impl<B: Backend> Batcher<B, ImageFolderItem, ImageFolderBatch<B>> for ImageFolderBatcher {
+    fn batch(&self, items: Vec<ImageFolderItem>, device: &B::Device) -> ImageFolderBatch<B> {
+        // image は CHW
+        let pixels: Vec<f32> = items.iter().flat_map(|item| item.image.iter().copied()).collect();
+        let images = preprocess(&self.preprocessor, self.augmenter.as_ref(), pixels, items.len(), device);
 
         let targets = items
             .iter()
@@ -79,52 +221,123 @@ impl<B: Backend> Batcher<B, Cifar10Item, CifarBatch<B>> for CifarBatcher {
                 Tensor::<B, 1, Int>::from_data([(item.label as i64).elem::<B::IntElem>()], device)
             })
             .collect();
-
-        let images = Tensor::cat(images, 0).reshape([-1, 3, 32, 32]); // [B,3,32,32]
         let targets = Tensor::cat(targets, 0); // [B]
 
-        CifarBatch { images, targets }
+        ImageFolderBatch { images, targets }
     }
 }
 
-// CIFAR-10データセットの実装
-pub struct Cifar10Dataset {
-    samples: Vec<Cifar10Item>,
+/// データセットを読み込めない理由
+#[derive(Debug)]
+pub enum DatasetError {
+    /// データのディレクトリ・ファイルがない (`download` はダウンロード用のスクリプト)
+    NotFound {
+        dataset: &'static str,
+        path: PathBuf,
+        download: &'static str,
+    },
+    /// ファイルはあるが読めない・形式が違う
+    Invalid { path: PathBuf, reason: String },
 }
 
-impl Cifar10Dataset {
-    pub fn train() -> Self {
-        match Self::load_cifar10_binary(true) {
-            Ok(dataset) => {
-                println!(
-                    "✓ CIFAR-10訓練データを読み込みました: {} samples",
-                    dataset.samples.len()
-                );
-                dataset
-            }
-            Err(e) => {
-                println!("⚠ CIFAR-10データの読み込みに失敗: {}", e);
-                println!("  データセットをダウンロードするには:");
-                println!("  .\\scripts\\download_cifar10.ps1");
-                println!("  ダミーデータで代用します...");
-                Self::create_dummy_dataset(50000)
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::NotFound {
+                dataset,
+                path,
+                download,
+            } => write!(
+                f,
+                "{}データが見つかりません: {} (.\\scripts\\{} でダウンロードしてください)",
+                dataset,
+                path.display(),
+                download
+            ),
+            DatasetError::Invalid { path, reason } => {
+                write!(f, "データファイルを読み込めません {}: {}", path.display(), reason)
             }
         }
     }
+}
 
-    pub fn test() -> Self {
-        match Self::load_cifar10_binary(false) {
-            Ok(dataset) => {
-                println!(
-                    "✓ CIFAR-10テストデータを読み込みました: {} samples",
-                    dataset.samples.len()
-                );
-                dataset
-            }
-            Err(_) => Self::create_dummy_dataset(10000),
+impl std::error::Error for DatasetError {}
+
+/// CIFAR バイナリ形式のレコードのラベル部分
+#[derive(Clone, Copy, Debug)]
+pub enum CifarLabels {
+    /// 1バイト (0-9)
+    Cifar10,
+    /// 2バイト (coarse 0-19, fine 0-99) のどちらを使うか
+    Cifar100(Cifar100Label),
+}
+
+impl CifarLabels {
+    fn label_bytes(self) -> usize {
+        match self {
+            CifarLabels::Cifar10 => 1,
+            CifarLabels::Cifar100(_) => 2,
+        }
+    }
+
+    fn label_index(self) -> usize {
+        match self {
+            CifarLabels::Cifar10 | CifarLabels::Cifar100(Cifar100Label::Coarse) => 0,
+            CifarLabels::Cifar100(Cifar100Label::Fine) => 1,
         }
     }
 
+    fn not_found(self, path: &Path) -> DatasetError {
+        let (dataset, download) = match self {
+            CifarLabels::Cifar10 => ("CIFAR-10", "download_cifar10.ps1"),
+            CifarLabels::Cifar100(_) => ("CIFAR-100", "download_cifar100.ps1"),
+        };
+        DatasetError::NotFound {
+            dataset,
+            path: path.to_path_buf(),
+            download,
+        }
+    }
+}
+
+// CIFAR-10 / CIFAR-100 データセットの実装
+pub struct CifarDataset {
+    samples: Vec<CifarItem>,
+}
+
+impl CifarDataset {
+    /// CIFAR-10 バイナリ (`dir/data_batch_{1..5}.bin` または `dir/test_batch.bin`)
+    ///
+    /// `synthetic` ならファイルを読まずに合成データ (学習しても意味のないノイズ) を返す。
+    pub fn cifar10(dir: &str, synthetic: bool, is_train: bool) -> Result<Self, DatasetError> {
+        let split = if is_train { "訓練" } else { "テスト" };
+        if synthetic {
+            let dataset = Self::create_dummy_dataset(if is_train { 50000 } else { 10000 });
+            println!(
+                "⚠ CIFAR-10{}データの代わりに合成データを使います (動作確認用): {} samples",
+                split,
+                dataset.samples.len()
+            );
+            return Ok(dataset);
+        }
+
+        let data_dir = Path::new(dir);
+        if !data_dir.is_dir() {
+            return Err(CifarLabels::Cifar10.not_found(data_dir));
+        }
+        let files: Vec<String> = if is_train {
+            (1..=5).map(|i| format!("data_batch_{}.bin", i)).collect()
+        } else {
+            vec!["test_batch.bin".to_string()]
+        };
+        let mut samples = Vec::new();
+        for file in files {
+            samples.extend(Self::read_cifar_batch(&data_dir.join(file), CifarLabels::Cifar10)?);
+        }
+        println!("✓ CIFAR-10{}データを読み込みました: {} samples", split, samples.len());
+        Ok(CifarDataset { samples })
+    }
+
     fn create_dummy_dataset(size: usize) -> Self {
         use std::collections::hash_map::DefaultHasher;
         use std::hash::{Hash, Hasher};
@@ -138,81 +351,77 @@ impl Cifar10Dataset {
             i.hash(&mut hasher);
             let seed = hasher.finish();
 
-            for c in 0..3 {
-                for h in 0..32 {
-                    for w in 0..32 {
+            for (c, channel) in image.iter_mut().enumerate() {
+                for (h, row) in channel.iter_mut().enumerate() {
+                    for (w, pixel) in row.iter_mut().enumerate() {
                         // チャンネルと位置に基づいたパターンを生成
-                        let val = ((seed as usize + c * 1000 + h * 10 + w) % 256) as f32;
-                        image[c][h][w] = val;
+                        *pixel = ((seed as usize + c * 1000 + h * 10 + w) % 256) as f32;
                     }
                 }
             }
 
             let label = (seed % 10) as usize; // 10クラス
-            samples.push(Cifar10Item { image, label });
+            samples.push(CifarItem { image, label });
         }
 
-        Cifar10Dataset { samples }
+        CifarDataset { samples }
     }
 
-    fn load_cifar10_binary(is_train: bool) -> Result<Self, Box<dyn std::error::Error>> {
-        let data_dir = Path::new("datasets/cifar-10/cifar-10-batches-bin");
-
-        if !data_dir.exists() {
-            return Err(
-                format!("CIFAR-10データディレクトリが見つかりません: {:?}", data_dir).into(),
-            );
-        }
-
-        if is_train {
-            // 訓練データ: data_batch_1.bin ~ data_batch_5.bin
-            let mut all_samples = Vec::new();
-            for i in 1..=5 {
-                let batch_path = data_dir.join(format!("data_batch_{}.bin", i));
-                let batch_samples = Self::read_cifar_batch(&batch_path)?;
-                all_samples.extend(batch_samples);
-            }
-            Ok(Cifar10Dataset {
-                samples: all_samples,
-            })
-        } else {
-            // テストデータ: test_batch.bin
-            let test_path = data_dir.join("test_batch.bin");
-            let samples = Self::read_cifar_batch(&test_path)?;
-            Ok(Cifar10Dataset { samples })
-        }
+    /// CIFAR-100 バイナリ (`dir/train.bin` または `dir/test.bin`)
+    pub fn cifar100(dir: &str, label: Cifar100Label, is_train: bool) -> Result<Self, DatasetError> {
+        let path = Path::new(dir).join(if is_train { "train.bin" } else { "test.bin" });
+        let samples = Self::read_cifar_batch(&path, CifarLabels::Cifar100(label))?;
+        println!(
+            "✓ CIFAR-100{}データを読み込みました ({} label): {} samples",
+            if is_train { "訓練" } else { "テスト" },
+            label.kind(),
+            samples.len()
+        );
+        Ok(CifarDataset { samples })
     }
 
-    fn read_cifar_batch(path: &Path) -> Result<Vec<Cifar10Item>, Box<dyn std::error::Error>> {
-        if !path.exists() {
-            return Err(format!("バッチファイルが見つかりません: {:?}", path).into());
-        }
-
-        let file = File::open(path)?;
+    fn read_cifar_batch(path: &Path, labels: CifarLabels) -> Result<Vec<CifarItem>, DatasetError> {
+        let file = File::open(path).map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => labels.not_found(path),
+            _ => DatasetError::Invalid {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            },
+        })?;
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0) as usize;
         let mut reader = BufReader::new(file);
         let mut samples = Vec::new();
 
         // CIFAR-10バイナリ形式: 各サンプルは3073バイト
         // 1バイト目: ラベル (0-9)
+        // CIFAR-100バイナリ形式: 各サンプルは3074バイト
+        // 1バイト目: coarse ラベル (0-19), 2バイト目: fine ラベル (0-99)
         // 残り3072バイト: 32x32x3の画像データ (R,G,B順)
-        let mut buffer = [0u8; 3073];
-
-        while reader.read_exact(&mut buffer).is_ok() {
-            let label = buffer[0] as usize;
+        let label_bytes = labels.label_bytes();
+        let record_len = label_bytes + 3072;
+        if len == 0 || !len.is_multiple_of(record_len) {
+            return Err(DatasetError::Invalid {
+                path: path.to_path_buf(),
+                reason: format!("ファイルサイズ {} bytes が {} bytes のレコードの倍数ではありません", len, record_len),
+            });
+        }
+        let mut buffer = vec![0u8; record_len];
+
+        for _ in 0..len / record_len {
+            reader.read_exact(&mut buffer).map_err(|e| DatasetError::Invalid {
+                path: path.to_path_buf(),
+                reason: e.to_string(),
+            })?;
+            let label = buffer[labels.label_index()] as usize;
             let mut image = [[[0.0f32; 32]; 32]; 3];
 
-            let mut idx = 1;
             // RGBの順で読み込み
-            for c in 0..3 {
-                for h in 0..32 {
-                    for w in 0..32 {
-                        image[c][h][w] = buffer[idx] as f32;
-                        idx += 1;
-                    }
-                }
+            let pixels = image.iter_mut().flatten().flatten();
+            for (pixel, &byte) in pixels.zip(&buffer[label_bytes..]) {
+                *pixel = byte as f32;
             }
 
-            samples.push(Cifar10Item { image, label });
+            samples.push(CifarItem { image, label });
         }
 
         println!(
@@ -224,8 +433,8 @@ impl Cifar10Dataset {
     }
 }
 
-impl burn::data::dataset::Dataset<Cifar10Item> for Cifar10Dataset {
-    fn get(&self, index: usize) -> Option<Cifar10Item> {
+impl Dataset<CifarItem> for CifarDataset {
+    fn get(&self, index: usize) -> Option<CifarItem> {
         self.samples.get(index).cloned()
     }
 
@@ -234,27 +443,110 @@ impl burn::data::dataset::Dataset<Cifar10Item> for Cifar10Dataset {
     }
 }
 
-// CIFAR-10クラス名の定義
-pub const CIFAR10_CLASSES: [&str; 10] = [
-    "airplane",
-    "automobile",
-    "bird",
-    "cat",
-    "deer",
-    "dog",
-    "frog",
-    "horse",
-    "ship",
-    "truck",
-];
-
-// 便利メソッド
-impl Cifar10Dataset {
-    pub fn get_class_name(label: usize) -> &'static str {
-        CIFAR10_CLASSES.get(label).map_or("unknown", |v| v)
+/// 組み込み MNIST の IDX ファイルを置くディレクトリ
+pub const MNIST_DIR: &str = "datasets/mnist";
+
+/// MNIST 系 (組み込み mnist と `idx`) の学習用またはテスト用データ
+pub fn mnist_family(config: &DatasetConfig, is_train: bool) -> Result<Box<dyn Dataset<MnistItem>>> {
+    match &config.source {
+        DatasetSource::Idx {
+            dir,
+            transpose,
+            label_offset,
+        } => Ok(Box::new(IdxDataset::load(dir, is_train, *transpose, *label_offset, config.num_classes)?)),
+        DatasetSource::Builtin if idx::has_files(MNIST_DIR) => {
+            Ok(Box::new(IdxDataset::load(MNIST_DIR, is_train, false, 0, config.num_classes)?))
+        }
+        DatasetSource::Builtin if is_train => Ok(Box::new(MnistDataset::train())),
+        DatasetSource::Builtin => Ok(Box::new(MnistDataset::test())),
+        DatasetSource::Cifar10 { .. } | DatasetSource::Cifar100 { .. } | DatasetSource::ImageFolder { .. } => {
+            Err(anyhow!("{} は MNIST 系のデータセットではありません", config.name))
+        }
+    }
+}
+
+/// 学習データを分割した片方
+pub type SplitDataset<D, I> = PartialDataset<Arc<ShuffledDataset<D, I>>, I>;
+
+/// 学習データをシード固定でシャッフルし、(学習用, 検証用) に分割
+pub fn split_validation<D, I>(
+    dataset: D,
+    config: &ValidationConfig,
+) -> Result<(SplitDataset<D, I>, SplitDataset<D, I>)>
+where
+    D: Dataset<I>,
+    I: Clone + Send + Sync,
+{
+    if !(config.fraction > 0.0 && config.fraction < 1.0) {
+        return Err(anyhow!(
+            "validation.fraction は 0 より大きく 1 より小さい値にしてください: {}",
+            config.fraction
+        ));
+    }
+
+    let len = dataset.len();
+    if len < 2 {
+        return Err(anyhow!("学習データが少なすぎて検証用に分割できません: {} samples", len));
+    }
+    let val_len = ((len as f64 * config.fraction).round() as usize).clamp(1, len - 1);
+
+    let shuffled = Arc::new(ShuffledDataset::with_seed(dataset, config.seed));
+    let val = PartialDataset::new(shuffled.clone(), 0, val_len);
+    let train = PartialDataset::new(shuffled, val_len, len);
+    Ok((train, val))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use burn::data::dataset::InMemDataset;
+
+    #[test]
+    fn split_validation_is_sized_disjoint_and_deterministic() {
+        let config = ValidationConfig {
+            fraction: 0.1,
+            seed: 42,
+        };
+        let split = || split_validation(InMemDataset::new((0..95usize).collect()), &config).unwrap();
+        let (train, val) = split();
+        assert_eq!((train.len(), val.len()), (85, 10)); // 9.5 は四捨五入で 10
+
+        let mut all: Vec<usize> = train.iter().chain(val.iter()).collect();
+        all.sort();
+        assert_eq!(all, (0..95).collect::<Vec<_>>());
+
+        let (train2, val2) = split();
+        assert_eq!(val.iter().collect::<Vec<_>>(), val2.iter().collect::<Vec<_>>());
+        assert_eq!(train.iter().collect::<Vec<_>>(), train2.iter().collect::<Vec<_>>());
+        assert_ne!(val.iter().collect::<Vec<_>>(), (0..10).collect::<Vec<_>>(), "not shuffled");
+
+        // 最低1件ずつ残す・不正な割合は拒否
+        let (train, val) = split_validation(InMemDataset::new(vec![1, 2]), &config).unwrap();
+        assert_eq!((train.len(), val.len()), (1, 1));
+        let bad = ValidationConfig { fraction: 1.0, seed: 0 };
+        assert!(split_validation(InMemDataset::new(vec![1, 2]), &bad).is_err());
+        assert!(split_validation(InMemDataset::new(vec![1]), &config).is_err());
     }
 
-    pub fn class_count() -> usize {
-        10
+    #[test]
+    fn missing_or_truncated_cifar_data_is_an_error() {
+        let dir = std::env::temp_dir().join(format!("cifar-test-{}", std::process::id()));
+        let missing = dir.join("missing");
+        assert!(matches!(
+            CifarDataset::cifar10(&missing.to_string_lossy(), false, true),
+            Err(DatasetError::NotFound { .. })
+        ));
+        assert_eq!(CifarDataset::cifar10(&missing.to_string_lossy(), true, false).unwrap().len(), 10000);
+
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("test.bin"), vec![0u8; 3074 * 2 + 1]).unwrap();
+        assert!(matches!(
+            CifarDataset::cifar100(&dir.to_string_lossy(), Cifar100Label::Fine, false),
+            Err(DatasetError::Invalid { .. })
+        ));
+        std::fs::write(dir.join("test.bin"), [[3u8, 42].as_slice(), &[0; 3072]].concat()).unwrap();
+        let coarse = CifarDataset::cifar100(&dir.to_string_lossy(), Cifar100Label::Coarse, false).unwrap();
+        assert_eq!(coarse.get(0).unwrap().label, 3);
+        std::fs::remove_dir_all(&dir).unwrap();
     }
 }
@@ -0,0 +1,512 @@
+// src/eval.rs
+use crate::config::{DatasetConfig, DatasetSource};
+use crate::data::{self, CifarBatcher, CifarDataset, ImageBatch, ImageFolderBatcher, MnistBatcher};
+use crate::image_folder::{ImageFolder, Split};
+use crate::image_files::{self, FileFilter, ImageFile};
+use crate::infer::{self, BatchOptions};
+use crate::model::ModelTrait;
+use crate::preprocess::Preprocessor;
+use crate::registry::{self, ModelSource};
+use anyhow::{Result, anyhow};
+use burn::{
+    data::{
+        dataloader::{DataLoaderBuilder, batcher::Batcher},
+        dataset::Dataset,
+    },
+    prelude::*,
+};
+use serde::Serialize;
+use std::{fmt::Debug, fs, path::{Component, Path}};
+
+/// クラスごとの評価指標
+#[derive(Debug, Clone, Serialize)]
+pub struct ClassMetrics {
+    pub class_name: String,
+    pub precision: f32,
+    pub recall: f32,
+    pub f1: f32,
+    pub support: usize,
+}
+
+/// `eval` サブコマンドの結果 (JSONとしても保存する)
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalReport {
+    pub dataset: String,
+    pub model_path: String,
+    /// `--folder` で評価したディレクトリ (None ならテストデータ)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    pub samples: usize,
+    pub correct: usize,
+    pub accuracy: f32,
+    pub per_class: Vec<ClassMetrics>,
+    /// 行 = 正解ラベル, 列 = 予測ラベル
+    pub confusion_matrix: Vec<Vec<usize>>,
+    /// `--folder` 評価のみ: 誤分類した画像
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub misclassified: Vec<Misclassified>,
+    /// `--folder` 評価のみ: 読み込めなかった画像
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FileError>,
+}
+
+/// 誤分類した画像1枚
+#[derive(Debug, Clone, Serialize)]
+pub struct Misclassified {
+    /// 評価ディレクトリからの相対パス
+    pub file: String,
+    pub target: String,
+    pub predicted: String,
+    /// 予測クラスの確率
+    pub probability: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FileError {
+    pub file: String,
+    pub error: String,
+}
+
+/// 混同行列 (行 = 正解, 列 = 予測)
+#[derive(Debug, Clone)]
+pub struct ConfusionMatrix {
+    counts: Vec<Vec<usize>>,
+}
+
+impl ConfusionMatrix {
+    pub fn new(num_classes: usize) -> Self {
+        Self {
+            counts: vec![vec![0; num_classes]; num_classes],
+        }
+    }
+
+    /// 1件を数える (クラス数の範囲外はモデルと設定が合っていないのでエラー)
+    pub fn add(&mut self, target: usize, pred: usize) -> Result<()> {
+        let n = self.counts.len();
+        if target >= n || pred >= n {
+            return Err(anyhow!(
+                "正解ラベル {} / 予測 {} が num_classes ({}) の範囲外です (モデル・データと設定のクラス数が合っているか確認してください)",
+                target,
+                pred,
+                n
+            ));
+        }
+        self.counts[target][pred] += 1;
+        Ok(())
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().map(|row| row.iter().sum::<usize>()).sum()
+    }
+
+    pub fn correct(&self) -> usize {
+        (0..self.counts.len()).map(|i| self.counts[i][i]).sum()
+    }
+
+    pub fn class_metrics(&self, class_names: &[String]) -> Vec<ClassMetrics> {
+        let n = self.counts.len();
+        (0..n)
+            .map(|c| {
+                let tp = self.counts[c][c];
+                let support: usize = self.counts[c].iter().sum();
+                let predicted: usize = self.counts.iter().map(|row| row[c]).sum();
+
+                let precision = ratio(tp, predicted);
+                let recall = ratio(tp, support);
+                let f1 = if precision + recall > 0.0 {
+                    2.0 * precision * recall / (precision + recall)
+                } else {
+                    0.0
+                };
+
+                ClassMetrics {
+                    class_name: class_names
+                        .get(c)
+                        .cloned()
+                        .unwrap_or_else(|| format!("unknown_{}", c)),
+                    precision,
+                    recall,
+                    f1,
+                    support,
+                }
+            })
+            .collect()
+    }
+
+    pub fn into_counts(self) -> Vec<Vec<usize>> {
+        self.counts
+    }
+}
+
+fn ratio(num: usize, den: usize) -> f32 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f32 / den as f32
+    }
+}
+
+/// 保存済みモデルをテストデータで評価
+pub fn evaluate<B: Backend>(config: &DatasetConfig, device: &B::Device) -> Result<EvalReport> {
+    let model_path = config.get_model_path();
+    let model = registry::load_model::<B>(config, device, ModelSource::File(&model_path))?;
+
+    let matrix = match (&config.source, config.name.as_str()) {
+        (DatasetSource::Builtin, "mnist") | (DatasetSource::Idx { .. }, _) => {
+            confusion_matrix(config, model.as_ref(), device, MnistBatcher::new(config)?, data::mnist_family(config, false)?)
+        }
+        (DatasetSource::Cifar10 { dir, synthetic }, _) => {
+            confusion_matrix(config, model.as_ref(), device, CifarBatcher::new(config)?, CifarDataset::cifar10(dir, *synthetic, false)?)
+        }
+        (DatasetSource::Cifar100 { dir, label }, _) => {
+            confusion_matrix(config, model.as_ref(), device, CifarBatcher::new(config)?, CifarDataset::cifar100(dir, *label, false)?)
+        }
+        (DatasetSource::Builtin, name) => return Err(anyhow!("未対応のデータセット: {}", name)),
+        (DatasetSource::ImageFolder { .. }, _) => {
+            let test = ImageFolder::scan(config)?.dataset(Split::Test, &Preprocessor::new(config)?)?;
+            confusion_matrix(config, model.as_ref(), device, ImageFolderBatcher::new(config)?, test)
+        }
+    }?;
+
+    let samples = matrix.total();
+    let correct = matrix.correct();
+    let per_class = matrix.class_metrics(&config.class_names);
+
+    Ok(EvalReport {
+        dataset: config.name.clone(),
+        model_path,
+        folder: None,
+        samples,
+        correct,
+        accuracy: ratio(correct, samples),
+        per_class,
+        confusion_matrix: matrix.into_counts(),
+        misclassified: Vec::new(),
+        errors: Vec::new(),
+    })
+}
+
+/// `dir/<クラス名>/...` の画像を、サブディレクトリ名を正解ラベルとして評価
+///
+/// クラス名は `class_names` と一致するもののみ対象 (それ以外のディレクトリと直下のファイルは警告して除外)。
+pub fn evaluate_folder<B: Backend>(
+    config: &DatasetConfig,
+    dir: &str,
+    device: &B::Device,
+) -> Result<EvalReport> {
+    if !Path::new(dir).is_dir() {
+        return Err(anyhow!("評価ディレクトリが見つかりません: {}", dir));
+    }
+    let filter = FileFilter {
+        recursive: true,
+        ..Default::default()
+    };
+
+    let mut files = Vec::new();
+    let mut targets = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+    for file in image_files::collect(dir, &filter)? {
+        match folder_label(Path::new(dir), &file, &config.class_names) {
+            Some(label) => {
+                files.push(file);
+                targets.push(label);
+            }
+            None => skipped.push(file.relative),
+        }
+    }
+    if !skipped.is_empty() {
+        eprintln!(
+            "(warn) クラス名のサブディレクトリにない画像 {} 件を除外しました (例: {})",
+            skipped.len(),
+            skipped[0]
+        );
+    }
+    if files.is_empty() {
+        return Err(anyhow!(
+            "{} にクラス名 ({}) のサブディレクトリの画像がありません",
+            dir,
+            config.class_names.join(", ")
+        ));
+    }
+
+    let model_path = config.get_model_path();
+    let model = registry::load_model::<B>(config, device, ModelSource::File(&model_path))?;
+    let batch = BatchOptions::new(config.training.batch_size, None);
+    let results = infer::predict_files(config, model.as_ref(), device, &files, 1, batch, None)?;
+
+    let mut matrix = ConfusionMatrix::new(config.num_classes);
+    let mut misclassified = Vec::new();
+    let mut errors = Vec::new();
+    for ((file, target), result) in files.iter().zip(targets).zip(results) {
+        match result {
+            Ok(prediction) => {
+                let top = &prediction.predictions[0];
+                matrix.add(target, top.class_index)?;
+                if top.class_index != target {
+                    misclassified.push(Misclassified {
+                        file: file.relative.clone(),
+                        target: config.class_names[target].clone(),
+                        predicted: top.class_name.clone(),
+                        probability: top.probability,
+                    });
+                }
+            }
+            Err(e) => errors.push(FileError {
+                file: file.relative.clone(),
+                error: e.to_string(),
+            }),
+        }
+    }
+
+    let samples = matrix.total();
+    let correct = matrix.correct();
+    let per_class = matrix.class_metrics(&config.class_names);
+
+    Ok(EvalReport {
+        dataset: config.name.clone(),
+        model_path,
+        folder: Some(dir.to_string()),
+        samples,
+        correct,
+        accuracy: ratio(correct, samples),
+        per_class,
+        confusion_matrix: matrix.into_counts(),
+        misclassified,
+        errors,
+    })
+}
+
+/// `dir` 直下のサブディレクトリ名 (最初のパス要素) をクラス名として正解ラベルを得る
+///
+/// 入れ子のディレクトリでも最上位のクラスディレクトリに属し、`dir` 直下のファイルはラベルなし。
+fn folder_label(dir: &Path, file: &ImageFile, class_names: &[String]) -> Option<usize> {
+    let mut components = file.path.strip_prefix(dir).ok()?.components();
+    let class = match components.next()? {
+        Component::Normal(name) => name.to_str()?,
+        _ => return None,
+    };
+    components.next()?;
+    class_names.iter().position(|c| c == class)
+}
+
+fn confusion_matrix<B, I, O>(
+    config: &DatasetConfig,
+    model: &dyn ModelTrait<B>,
+    device: &B::Device,
+    batcher: impl Batcher<B, I, O> + 'static,
+    dataset: impl Dataset<I> + 'static,
+) -> Result<ConfusionMatrix>
+where
+    B: Backend,
+    I: Send + Sync + Clone + Debug + 'static,
+    O: ImageBatch<B> + Clone + Send + Debug + 'static,
+{
+    let loader = DataLoaderBuilder::new(batcher)
+        .batch_size(config.training.batch_size)
+        .set_device(device.clone())
+        .build(dataset);
+
+    let mut matrix = ConfusionMatrix::new(config.num_classes);
+    for batch in loader.iter() {
+        let logits = model.forward(batch.images().clone());
+        accumulate(&mut matrix, logits, batch.targets().clone())?;
+    }
+    Ok(matrix)
+}
+
+fn accumulate<B: Backend>(
+    matrix: &mut ConfusionMatrix,
+    logits: Tensor<B, 2>,
+    targets: Tensor<B, 1, Int>,
+) -> Result<()> {
+    let preds = logits.argmax(1).reshape([-1]).into_data();
+    let targets = targets.into_data();
+    for (target, pred) in targets.iter::<i64>().zip(preds.iter::<i64>()) {
+        matrix.add(target as usize, pred as usize)?;
+    }
+    Ok(())
+}
+
+/// 評価結果を表形式で表示
+pub fn print_report(report: &EvalReport) {
+    if let Some(folder) = &report.folder {
+        println!("folder {}", folder);
+    }
+    println!(
+        "dataset {} | model {} | accuracy {:.2}% ({}/{} samples)",
+        report.dataset,
+        report.model_path,
+        report.accuracy * 100.0,
+        report.correct,
+        report.samples
+    );
+    println!();
+
+    let name_width = report
+        .per_class
+        .iter()
+        .map(|m| m.class_name.len())
+        .max()
+        .unwrap_or(0)
+        .max("class".len());
+
+    println!(
+        "{:<name_width$} | precision | recall |     f1 | support",
+        "class"
+    );
+    println!("{}", "-".repeat(name_width + 39));
+    for m in &report.per_class {
+        println!(
+            "{:<name_width$} | {:>9.4} | {:>6.4} | {:>6.4} | {:>7}",
+            m.class_name, m.precision, m.recall, m.f1, m.support
+        );
+    }
+    println!();
+
+    // 混同行列: 行 = 正解, 列 = 予測 (列見出しはクラス番号)
+    let cell = report
+        .confusion_matrix
+        .iter()
+        .flatten()
+        .map(|v| v.to_string().len())
+        .max()
+        .unwrap_or(1)
+        .max(report.per_class.len().to_string().len());
+
+    println!("confusion matrix (row = true, col = pred)");
+    let header: Vec<String> = (0..report.confusion_matrix.len())
+        .map(|i| format!("{:>cell$}", i))
+        .collect();
+    println!("{:<name_width$} | {}", "", header.join(" "));
+    for (m, row) in report.per_class.iter().zip(&report.confusion_matrix) {
+        let cells: Vec<String> = row.iter().map(|v| format!("{:>cell$}", v)).collect();
+        println!("{:<name_width$} | {}", m.class_name, cells.join(" "));
+    }
+
+    if !report.misclassified.is_empty() {
+        println!();
+        println!("misclassified ({})", report.misclassified.len());
+        let file_width = report
+            .misclassified
+            .iter()
+            .map(|m| m.file.len())
+            .max()
+            .unwrap_or(0)
+            .max("file".len());
+        println!("{:<file_width$} | true | pred | probability", "file");
+        for m in &report.misclassified {
+            println!(
+                "{:<file_width$} | {} | {} | {:.2}%",
+                m.file,
+                m.target,
+                m.predicted,
+                m.probability * 100.0
+            );
+        }
+    }
+    if !report.errors.is_empty() {
+        println!();
+        println!("errors ({})", report.errors.len());
+        for e in &report.errors {
+            println!("{} | ERROR: {}", e.file, e.error);
+        }
+    }
+}
+
+/// 評価結果をJSONで保存し、保存先パスを返す
+/// (`--folder` 評価は `eval_folder.json`)
+pub fn save_report(config: &DatasetConfig, report: &EvalReport) -> Result<String> {
+    fs::create_dir_all(&config.artifacts.dir)?;
+    let path = match report.folder {
+        Some(_) => config.get_folder_eval_report_path(),
+        None => config.get_eval_report_path(),
+    };
+    let json = serde_json::to_string_pretty(report)?;
+    fs::write(&path, json).map_err(|e| anyhow!("評価結果の保存失敗 {}: {}", path, e))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_metrics_from_hand_computed_matrix() {
+        // 行 = 正解, 列 = 予測
+        // [2, 1, 0]
+        // [1, 3, 0]
+        // [1, 0, 0]  <- クラス2は一度も予測されない
+        let mut matrix = ConfusionMatrix::new(3);
+        for (target, pred, n) in [(0, 0, 2), (0, 1, 1), (1, 0, 1), (1, 1, 3), (2, 0, 1)] {
+            for _ in 0..n {
+                matrix.add(target, pred).unwrap();
+            }
+        }
+        // 範囲外は数えずにエラー (モデルと設定のクラス数の不一致)
+        let err = matrix.add(0, 7).unwrap_err();
+        assert!(err.to_string().contains("num_classes (3)"), "{err}");
+        assert!(matrix.add(3, 0).is_err());
+        assert_eq!(matrix.total(), 8);
+        assert_eq!(matrix.correct(), 5);
+
+        let names: Vec<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let metrics = matrix.class_metrics(&names);
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+
+        assert!(close(metrics[0].precision, 2.0 / 4.0));
+        assert!(close(metrics[0].recall, 2.0 / 3.0));
+        assert!(close(metrics[0].f1, 4.0 / 7.0));
+        assert_eq!(metrics[0].support, 3);
+
+        assert!(close(metrics[1].precision, 0.75));
+        assert!(close(metrics[1].recall, 0.75));
+        assert!(close(metrics[1].f1, 0.75));
+
+        // 予測 0 件・正解 0 件の割り算は 0 とする
+        assert_eq!(metrics[2].class_name, "unknown_2");
+        assert_eq!((metrics[2].precision, metrics[2].recall, metrics[2].f1), (0.0, 0.0, 0.0));
+        assert_eq!(metrics[2].support, 1);
+        assert_eq!(ratio(0, 0), 0.0);
+    }
+
+    #[test]
+    fn folder_label_uses_top_level_directory() {
+        let dir = std::env::temp_dir().join(format!("eval-folder-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for sub in ["cat/nested/deeper", "dog", "bird"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        // 形式判定は先頭バイトで行うので PNG シグネチャだけで足りる
+        let png = b"\x89PNG\r\n\x1a\n";
+        for file in ["cat/a.png", "cat/nested/deeper/b.png", "dog/c.png", "bird/d.png", "root.png"] {
+            fs::write(dir.join(file), png).unwrap();
+        }
+
+        let filter = FileFilter {
+            recursive: true,
+            ..Default::default()
+        };
+        let root = dir.to_str().unwrap();
+        let names: Vec<String> = ["cat", "dog"].iter().map(|s| s.to_string()).collect();
+        let labels: Vec<(String, Option<usize>)> = image_files::collect(root, &filter)
+            .unwrap()
+            .into_iter()
+            .map(|f| {
+                let label = folder_label(Path::new(root), &f, &names);
+                (f.relative, label)
+            })
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let expected = [
+            ("bird/d.png", None),
+            ("cat/a.png", Some(0)),
+            ("cat/nested/deeper/b.png", Some(0)),
+            ("dog/c.png", Some(1)),
+            ("root.png", None),
+        ];
+        let expected: Vec<(String, Option<usize>)> =
+            expected.iter().map(|(f, l)| (f.to_string(), *l)).collect();
+        assert_eq!(labels, expected);
+    }
+}
@@ -0,0 +1,224 @@
+// src/idx.rs
+// MNIST 系データセット (MNIST / Fashion-MNIST / KMNIST / EMNIST) の IDX 形式の読み込み
+use anyhow::{Result, anyhow};
+use burn::data::dataset::{Dataset, vision::MnistItem};
+use flate2::read::GzDecoder;
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+/// IDX ファイルの中身 (要素型は unsigned byte のみ対応)
+#[derive(Debug, Clone)]
+pub struct IdxArray {
+    pub dims: Vec<usize>,
+    pub data: Vec<u8>,
+}
+
+/// IDX ファイルを読み込む (先頭が gzip のマジックなら展開する)
+pub fn read_idx(path: &Path) -> Result<IdxArray> {
+    let bytes = fs::read(path).map_err(|e| anyhow!("IDXファイル読み込み失敗 {}: {}", path.display(), e))?;
+    let bytes = if bytes.starts_with(&[0x1f, 0x8b]) {
+        let mut raw = Vec::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut raw)
+            .map_err(|e| anyhow!("gzip 展開失敗 {}: {}", path.display(), e))?;
+        raw
+    } else {
+        bytes
+    };
+    parse_idx(&bytes).map_err(|e| anyhow!("IDXファイル解析失敗 {}: {}", path.display(), e))
+}
+
+/// マジック (0x00 0x00 型 次元数) → 次元ごとの大きさ (big-endian u32) → データ
+fn parse_idx(bytes: &[u8]) -> Result<IdxArray> {
+    let [0, 0, kind, ndims] = *bytes.get(..4).ok_or_else(|| anyhow!("ヘッダが短すぎます"))? else {
+        return Err(anyhow!("IDX のマジックナンバーではありません"));
+    };
+    if kind != 0x08 {
+        return Err(anyhow!("未対応の要素型: 0x{:02x} (unsigned byte のみ対応)", kind));
+    }
+
+    let header_len = 4 + 4 * ndims as usize;
+    let dims: Vec<usize> = bytes
+        .get(4..header_len)
+        .ok_or_else(|| anyhow!("ヘッダが短すぎます"))?
+        .chunks(4)
+        .map(|d| u32::from_be_bytes([d[0], d[1], d[2], d[3]]) as usize)
+        .collect();
+    let len: usize = dims.iter().product();
+    let data = &bytes[header_len..];
+    if data.len() != len {
+        return Err(anyhow!(
+            "データの長さが一致しません: {} bytes (期待値 {} = {:?})",
+            data.len(),
+            len,
+            dims
+        ));
+    }
+    Ok(IdxArray {
+        dims,
+        data: data.to_vec(),
+    })
+}
+
+/// `dir` の学習用 (`train-*`) またはテスト用 (`t10k-*` / `test-*`) の IDX ファイル
+///
+/// EMNIST の `emnist-letters-train-images-idx3-ubyte.gz` や `train-images.idx3-ubyte` のような名前も探す。
+fn find_file(dir: &Path, train: bool, kind: &str) -> Result<PathBuf> {
+    let splits: &[&str] = if train { &["train"] } else { &["t10k", "test"] };
+    let entries = fs::read_dir(dir).map_err(|e| anyhow!("データディレクトリを開けません {}: {}", dir.display(), e))?;
+
+    let mut found: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let name = name.strip_suffix(".gz").unwrap_or(&name).replace('.', "-");
+            splits.iter().any(|s| name.ends_with(&format!("{s}-{kind}")))
+        })
+        .collect();
+    found.sort();
+    match found.len() {
+        1 => Ok(found.remove(0)),
+        0 => Err(anyhow!(
+            "{} に {}-{} がありません",
+            dir.display(),
+            splits.join("/"),
+            kind
+        )),
+        _ => Err(anyhow!(
+            "{} に {}-{} が複数あります (データセットごとにディレクトリを分けてください): {:?}",
+            dir.display(),
+            splits.join("/"),
+            kind,
+            found
+        )),
+    }
+}
+
+/// `dir` に学習用・テスト用の IDX ファイルが揃っているか
+pub fn has_files(dir: &str) -> bool {
+    let dir = Path::new(dir);
+    [true, false].iter().all(|&train| {
+        find_file(dir, train, "images-idx3-ubyte").is_ok() && find_file(dir, train, "labels-idx1-ubyte").is_ok()
+    })
+}
+
+/// IDX ファイルから読み込んだ 28x28 グレースケール画像 (MNIST と同じ形)
+pub struct IdxDataset {
+    items: Vec<MnistItem>,
+}
+
+impl IdxDataset {
+    /// `transpose`: 画像が列優先で保存されている (EMNIST)
+    /// `label_offset`: ラベルから引く値 (EMNIST letters は 1 始まり)
+    pub fn load(dir: &str, train: bool, transpose: bool, label_offset: u8, num_classes: usize) -> Result<Self> {
+        let dir = Path::new(dir);
+        let images_path = find_file(dir, train, "images-idx3-ubyte")?;
+        let labels_path = find_file(dir, train, "labels-idx1-ubyte")?;
+        let images = read_idx(&images_path)?;
+        let labels = read_idx(&labels_path)?;
+
+        let [count, 28, 28] = images.dims[..] else {
+            return Err(anyhow!(
+                "画像は [N, 28, 28] である必要があります: {:?} ({})",
+                images.dims,
+                images_path.display()
+            ));
+        };
+        if labels.dims != [count] {
+            return Err(anyhow!(
+                "画像 {} 枚に対してラベルの形が {:?} です ({})",
+                count,
+                labels.dims,
+                labels_path.display()
+            ));
+        }
+
+        let mut items = Vec::with_capacity(count);
+        for (pixels, &label) in images.data.chunks_exact(28 * 28).zip(&labels.data) {
+            let label = label
+                .checked_sub(label_offset)
+                .filter(|&l| (l as usize) < num_classes)
+                .ok_or_else(|| {
+                    anyhow!(
+                        "ラベル {} が範囲外です (label_offset {}, num_classes {}): {}",
+                        label,
+                        label_offset,
+                        num_classes,
+                        labels_path.display()
+                    )
+                })?;
+
+            let mut image = [[0.0f32; 28]; 28];
+            for (i, &p) in pixels.iter().enumerate() {
+                let (y, x) = if transpose { (i % 28, i / 28) } else { (i / 28, i % 28) };
+                image[y][x] = p as f32;
+            }
+            items.push(MnistItem { image, label });
+        }
+
+        println!(
+            "✓ {} を読み込みました: {} samples",
+            images_path.display(),
+            items.len()
+        );
+        Ok(Self { items })
+    }
+}
+
+impl Dataset<MnistItem> for IdxDataset {
+    fn get(&self, index: usize) -> Option<MnistItem> {
+        self.items.get(index).cloned()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::{Compression, write::GzEncoder};
+    use std::io::Write;
+
+    fn idx_bytes(dims: &[u32], data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![0, 0, 0x08, dims.len() as u8];
+        for d in dims {
+            bytes.extend_from_slice(&d.to_be_bytes());
+        }
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn parses_raw_and_gzipped_idx() {
+        let bytes = idx_bytes(&[2, 3], &[1, 2, 3, 4, 5, 6]);
+        let parsed = parse_idx(&bytes).unwrap();
+        assert_eq!(parsed.dims, [2, 3]);
+        assert_eq!(parsed.data, [1, 2, 3, 4, 5, 6]);
+
+        let dir = std::env::temp_dir().join(format!("idx-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("train-labels-idx1-ubyte.gz");
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(&bytes).unwrap();
+        fs::write(&path, gz.finish().unwrap()).unwrap();
+
+        assert_eq!(find_file(&dir, true, "labels-idx1-ubyte").unwrap(), path);
+        assert_eq!(read_idx(&path).unwrap().data, parsed.data);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_idx() {
+        assert!(parse_idx(&[0, 0]).is_err());
+        assert!(parse_idx(&idx_bytes(&[4], &[1, 2, 3])).is_err());
+        let mut float = idx_bytes(&[1], &[0, 0, 0, 0]);
+        float[2] = 0x0d;
+        assert!(parse_idx(&float).is_err());
+    }
+}
@@ -0,0 +1,219 @@
+// src/image_files.rs
+// 推論・評価・学習に使う画像ファイルの列挙と読み込み (拡張子ではなく中身で形式を判定する)
+use crate::preprocess::Preprocessor;
+use anyhow::{Result, anyhow};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use image::{DynamicImage, ImageFormat, ImageReader};
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+    thread,
+};
+
+/// 読み込みに対応する画像形式
+pub const SUPPORTED_FORMATS: [ImageFormat; 6] = [
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Bmp,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+    ImageFormat::Tiff,
+];
+
+/// 列挙した画像ファイル
+#[derive(Debug, Clone)]
+pub struct ImageFile {
+    pub path: PathBuf,
+    /// 指定ディレクトリからの相対パス ('/' 区切り, 単一ファイル指定時は指定したパス)
+    pub relative: String,
+}
+
+/// ディレクトリの走査条件
+///
+/// パターンは globset の書式 (`*`, `?`, `[a-z]`, `[!x]`, `{a,b}`, `**`) で、`*` と `?` は '/' に一致しない。
+/// '/' を含まないパターンはファイル名のみ、含む場合は相対パス全体と照合する。
+#[derive(Debug, Clone, Default)]
+pub struct FileFilter {
+    /// サブディレクトリも走査する
+    pub recursive: bool,
+    /// いずれかに一致するファイルのみ (空なら全て)
+    pub include: GlobSet,
+    /// いずれかに一致するファイルを除外
+    pub exclude: GlobSet,
+}
+
+impl FileFilter {
+    pub fn new(recursive: bool, include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            recursive,
+            include: glob_set(include)?,
+            exclude: glob_set(exclude)?,
+        })
+    }
+
+    fn accepts(&self, relative: &str) -> bool {
+        (self.include.is_empty() || self.include.is_match(relative)) && !self.exclude.is_match(relative)
+    }
+}
+
+fn glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut set = GlobSetBuilder::new();
+    for pattern in patterns {
+        let trimmed = pattern.trim_start_matches("./");
+        if trimmed.is_empty() {
+            return Err(anyhow!("空のパターンは指定できません"));
+        }
+        // ファイル名のパターンはどの深さのファイル名にも一致させる
+        let glob = if trimmed.contains('/') {
+            trimmed.to_string()
+        } else {
+            format!("**/{trimmed}")
+        };
+        let glob = GlobBuilder::new(&glob)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| anyhow!("パターンが不正です {pattern}: {}", e.kind()))?;
+        set.add(glob);
+    }
+    set.build().map_err(|e| anyhow!("パターンを組み立てられません: {e}"))
+}
+
+/// ファイルならそれ自身、ディレクトリなら条件に合う画像ファイルを相対パス順に列挙
+pub fn collect(path: &str, filter: &FileFilter) -> Result<Vec<ImageFile>> {
+    let meta = fs::metadata(path).map_err(|e| anyhow!("指定パスを開けません {path}: {e}"))?;
+    if meta.is_file() {
+        return Ok(vec![ImageFile {
+            path: PathBuf::from(path),
+            relative: path.to_string(),
+        }]);
+    }
+    if !meta.is_dir() {
+        return Err(anyhow!(
+            "指定パスがファイルでもディレクトリでもありません: {path}"
+        ));
+    }
+
+    let mut files = Vec::new();
+    walk(Path::new(path), "", filter, &mut files)?;
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(files)
+}
+
+fn walk(dir: &Path, prefix: &str, filter: &FileFilter, files: &mut Vec<ImageFile>) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(|e| anyhow!("ディレクトリ読み込み失敗 {}: {}", dir.display(), e))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let relative = format!("{prefix}{name}");
+
+        // シンボリックリンクのディレクトリは辿らない (循環を避ける)
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            if filter.recursive {
+                walk(&path, &format!("{relative}/"), filter, files)?;
+            }
+        } else if path.is_file() && filter.accepts(&relative) && sniff_format(&path).is_some() {
+            files.push(ImageFile { path, relative });
+        }
+    }
+    Ok(())
+}
+
+/// ファイル先頭のバイト列から対応形式を判定 (拡張子は見ない)
+pub fn sniff_format(path: &Path) -> Option<ImageFormat> {
+    let mut header = [0u8; 32];
+    let len = File::open(path).ok()?.read(&mut header).ok()?;
+    image::guess_format(&header[..len])
+        .ok()
+        .filter(|f| SUPPORTED_FORMATS.contains(f))
+}
+
+/// 中身から判定した形式で画像を読み込む
+pub fn open_image(path: &Path) -> Result<DynamicImage> {
+    let format = sniff_format(path)
+        .ok_or_else(|| anyhow!("対応する画像形式ではありません (PNG/JPEG/BMP/GIF/WebP/TIFF)"))?;
+    let mut reader = ImageReader::open(path)?;
+    reader.set_format(format);
+    Ok(reader.decode()?)
+}
+
+/// 画像を読み込んで CHW 順の画素にする (`jobs` スレッドで分担し、順序は `files` と同じ)
+pub fn load_pixels(preprocessor: &Preprocessor, files: &[ImageFile], jobs: usize) -> Vec<Result<Vec<f32>>> {
+    let load = |file: &ImageFile| -> Result<Vec<f32>> {
+        let img = open_image(&file.path)?;
+        preprocessor.image_pixels(&img)
+    };
+
+    let per_thread = files.len().div_ceil(jobs.max(1)).max(1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = files
+            .chunks(per_thread)
+            .map(|part| scope.spawn(move || part.iter().map(load).collect::<Vec<_>>()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().expect("image loader thread panicked"))
+            .collect()
+    })
+}
+
+/// 読み込みスレッド数の既定値 (CPU数)
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, path: &str) -> bool {
+        FileFilter::new(true, &[pattern.to_string()], &[]).unwrap().accepts(path)
+    }
+
+    #[test]
+    fn name_patterns_match_file_name_at_any_depth() {
+        assert!(glob("*.jpg", "a.jpg"));
+        assert!(glob("*.jpg", "cats/sub/a.jpg"));
+        assert!(!glob("*.jpg", "a.jpeg"));
+        assert!(glob("img_??.png", "x/img_01.png"));
+        assert!(!glob("img_??.png", "img_1.png"));
+    }
+
+    #[test]
+    fn path_patterns_match_whole_relative_path() {
+        assert!(glob("cats/*.png", "cats/a.png"));
+        assert!(!glob("cats/*.png", "cats/x/a.png"));
+        assert!(!glob("cats/*.png", "dogs/a.png"));
+        assert!(glob("**/test/*", "a/b/test/c.png"));
+        assert!(glob("**/test/*", "test/c.png"));
+        assert!(glob("cats/**", "cats/x/y.png"));
+    }
+
+    #[test]
+    fn character_classes() {
+        assert!(glob("[0-4].png", "3.png"));
+        assert!(!glob("[0-4].png", "7.png"));
+        assert!(glob("[!0-4].png", "7.png"));
+        assert!(glob("[ab]*", "b_1.png"));
+        assert!(glob("*.{png,jpg}", "a/b.jpg"));
+        // 閉じていない '[' は文字として扱わずエラーにする
+        assert!(FileFilter::new(false, &["[.png".into()], &[]).is_err());
+        assert!(FileFilter::new(false, &["./".into()], &[]).is_err());
+    }
+
+    #[test]
+    fn many_wildcards_do_not_backtrack_exponentially() {
+        let name = format!("{}.png", "a".repeat(64));
+        assert!(!glob(&format!("{}b", "*a".repeat(16)), &name));
+        assert!(!glob(&format!("{}/x", "**/*".repeat(8)), &format!("{}/y", "d/".repeat(16))));
+    }
+
+    #[test]
+    fn filter_combines_include_and_exclude() {
+        let filter = FileFilter::new(true, &["*.png".into(), "*.jpg".into()], &["**/skip/**".into()]).unwrap();
+        assert!(filter.accepts("a/b.png"));
+        assert!(filter.accepts("c.jpg"));
+        assert!(!filter.accepts("c.bmp"));
+        assert!(!filter.accepts("a/skip/b.png"));
+    }
+}
@@ -0,0 +1,259 @@
+// src/image_folder.rs
+// `root/<クラス名>/` 以下の画像ファイル (形式は中身で判定) を学習・評価用のデータセットにする
+use crate::config::{DatasetConfig, DatasetSource, SplitConfig};
+use crate::image_files::{self, FileFilter, ImageFile};
+use crate::preprocess::Preprocessor;
+use anyhow::{Result, anyhow};
+use burn::data::dataset::Dataset;
+use std::path::Path;
+
+/// 画像1枚 (前処理済みの CHW 順 0-255 の画素)
+#[derive(Clone, Debug)]
+pub struct ImageFolderItem {
+    pub image: Vec<f32>,
+    pub label: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Split {
+    Train,
+    Val,
+    Test,
+}
+
+impl Split {
+    fn name(self) -> &'static str {
+        match self {
+            Split::Train => "train",
+            Split::Val => "val",
+            Split::Test => "test",
+        }
+    }
+}
+
+/// 走査済みの画像フォルダ (ファイル一覧と分割のみで、画像はまだ読み込まない)
+pub struct ImageFolder {
+    root: String,
+    train: Vec<(ImageFile, usize)>,
+    val: Vec<(ImageFile, usize)>,
+    test: Vec<(ImageFile, usize)>,
+}
+
+impl ImageFolder {
+    /// クラスごとにファイルを列挙し、シード固定でシャッフルして `split` の割合で分ける
+    pub fn scan(config: &DatasetConfig) -> Result<Self> {
+        let DatasetSource::ImageFolder { root, split } = &config.source else {
+            return Err(anyhow!("{} は image_folder のデータセットではありません", config.name));
+        };
+        check_split(split)?;
+
+        let filter = FileFilter {
+            recursive: true,
+            ..Default::default()
+        };
+        let mut folder = Self {
+            root: root.clone(),
+            train: Vec::new(),
+            val: Vec::new(),
+            test: Vec::new(),
+        };
+        for (label, class) in config.class_names.iter().enumerate() {
+            let dir = Path::new(root).join(class);
+            let mut files = image_files::collect(&dir.to_string_lossy(), &filter)?;
+            for file in &mut files {
+                file.relative = format!("{class}/{}", file.relative);
+            }
+            if files.is_empty() {
+                eprintln!("(warn) クラス {class} の画像がありません: {}", dir.display());
+            }
+            shuffle(&mut files, split.seed.wrapping_add(label as u64));
+
+            // 検証・テストは四捨五入、残りを学習用にする
+            let len = files.len();
+            let val_len = ((len as f64 * split.val).round() as usize).min(len);
+            let test_len = ((len as f64 * split.test).round() as usize).min(len - val_len);
+            let mut files = files.into_iter().map(|f| (f, label));
+            folder.val.extend(files.by_ref().take(val_len));
+            folder.test.extend(files.by_ref().take(test_len));
+            folder.train.extend(files);
+        }
+        Ok(folder)
+    }
+
+    /// 分割の1つを読み込む (画像は全てデコードしてメモリに保持する)
+    pub fn dataset(&self, split: Split, preprocessor: &Preprocessor) -> Result<ImageFolderDataset> {
+        let entries = match split {
+            Split::Train => &self.train,
+            Split::Val => &self.val,
+            Split::Test => &self.test,
+        };
+        // テスト用は空でもよい (source.split.test = 0)
+        if entries.is_empty() && split != Split::Test {
+            return Err(anyhow!(
+                "{} の {} 用の画像がありません (source.split の割合を確認してください)",
+                self.root,
+                split.name()
+            ));
+        }
+
+        let files: Vec<ImageFile> = entries.iter().map(|(f, _)| f.clone()).collect();
+        let pixels = image_files::load_pixels(preprocessor, &files, image_files::default_jobs());
+
+        let mut items = Vec::with_capacity(entries.len());
+        let mut failed = Vec::new();
+        for ((file, label), result) in entries.iter().zip(pixels) {
+            match result {
+                Ok(image) => items.push(ImageFolderItem { image, label: *label }),
+                Err(e) => failed.push(format!("{}: {}", file.relative, e)),
+            }
+        }
+        if !failed.is_empty() {
+            eprintln!(
+                "(warn) {} の画像 {} 件を読み込めないため除外しました (例: {})",
+                split.name(),
+                failed.len(),
+                failed[0]
+            );
+        }
+        println!(
+            "✓ {} の {} データを読み込みました: {} samples",
+            self.root,
+            split.name(),
+            items.len()
+        );
+        Ok(ImageFolderDataset { items })
+    }
+}
+
+/// 画像フォルダの分割の1つ
+pub struct ImageFolderDataset {
+    items: Vec<ImageFolderItem>,
+}
+
+impl Dataset<ImageFolderItem> for ImageFolderDataset {
+    fn get(&self, index: usize) -> Option<ImageFolderItem> {
+        self.items.get(index).cloned()
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+
+fn check_split(split: &SplitConfig) -> Result<()> {
+    let ratios = [split.train, split.val, split.test];
+    if ratios.iter().any(|r| !(0.0..=1.0).contains(r)) || split.train <= 0.0 || split.val <= 0.0 {
+        return Err(anyhow!(
+            "source.split は 0 から 1 の割合にしてください (train と val は 0 より大きく): {:?}",
+            ratios
+        ));
+    }
+    if (ratios.iter().sum::<f64>() - 1.0).abs() > 1e-6 {
+        return Err(anyhow!("source.split の合計が 1 になりません: {:?}", ratios));
+    }
+    Ok(())
+}
+
+/// シード固定の Fisher-Yates シャッフル (splitmix64)
+fn shuffle<T>(items: &mut [T], seed: u64) {
+    let mut state = seed;
+    let mut next = || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+    for i in (1..items.len()).rev() {
+        let j = (next() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn relatives(entries: &[(ImageFile, usize)]) -> Vec<(String, usize)> {
+        entries.iter().map(|(f, label)| (f.relative.clone(), *label)).collect()
+    }
+
+    #[test]
+    fn scan_splits_each_class_stably_and_disjointly() {
+        let root = std::env::temp_dir().join(format!("image-folder-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        // 形式判定は先頭バイトで行うので PNG シグネチャだけで足りる
+        let png = b"\x89PNG\r\n\x1a\n";
+        let mut all = Vec::new();
+        for (class, label, count) in [("a", 0, 10), ("b", 1, 7)] {
+            fs::create_dir_all(root.join(class).join("nested")).unwrap();
+            for i in 0..count {
+                // サブディレクトリの画像も同じクラスに含める
+                let file = if i == 0 { format!("{class}/nested/{i}.png") } else { format!("{class}/{i}.png") };
+                fs::write(root.join(&file), png).unwrap();
+                all.push((file, label));
+            }
+        }
+        // class_names にないディレクトリは対象外
+        fs::create_dir_all(root.join("other")).unwrap();
+        fs::write(root.join("other/x.png"), png).unwrap();
+
+        let mut config: DatasetConfig =
+            serde_json::from_str(include_str!("../configs/custom_digits.json")).expect("config");
+        config.class_names = vec!["a".to_string(), "b".to_string()];
+        config.num_classes = 2;
+        let split = SplitConfig {
+            train: 0.6,
+            val: 0.2,
+            test: 0.2,
+            seed: 7,
+        };
+        config.source = DatasetSource::ImageFolder {
+            root: root.to_string_lossy().into_owned(),
+            split: split.clone(),
+        };
+        let folder = ImageFolder::scan(&config).unwrap();
+        let again = ImageFolder::scan(&config).unwrap();
+        config.source = DatasetSource::ImageFolder {
+            root: root.to_string_lossy().into_owned(),
+            split: SplitConfig { seed: 8, ..split },
+        };
+        let reseeded = ImageFolder::scan(&config).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+
+        let (train, val, test) = (relatives(&folder.train), relatives(&folder.val), relatives(&folder.test));
+        let count = |entries: &[(String, usize)], label| entries.iter().filter(|(_, l)| *l == label).count();
+        // クラスごとに四捨五入: a (10枚) は 2/2/6, b (7枚) は 1/1/5
+        assert_eq!((count(&val, 0), count(&test, 0), count(&train, 0)), (2, 2, 6));
+        assert_eq!((count(&val, 1), count(&test, 1), count(&train, 1)), (1, 1, 5));
+
+        // 3つの分割は重ならず、合わせると全ファイルになる
+        let mut union: Vec<_> = train.iter().chain(&val).chain(&test).cloned().collect();
+        union.sort();
+        all.sort();
+        assert_eq!(union, all);
+        assert!(union.iter().all(|(f, label)| f.starts_with(["a/", "b/"][*label])));
+
+        // 同じシードなら同じ分割・順序、シードを変えると変わる
+        assert_eq!(relatives(&again.train), train);
+        assert_eq!(relatives(&again.val), val);
+        assert_eq!(relatives(&again.test), test);
+        assert_ne!(relatives(&reseeded.train), train);
+    }
+
+    #[test]
+    fn shuffle_is_a_seeded_permutation() {
+        let shuffled = |seed| {
+            let mut items: Vec<u32> = (0..20).collect();
+            shuffle(&mut items, seed);
+            items
+        };
+        assert_eq!(shuffled(3), shuffled(3));
+        assert_ne!(shuffled(3), shuffled(4));
+        assert_ne!(shuffled(3), (0..20).collect::<Vec<_>>());
+        let mut sorted = shuffled(3);
+        sorted.sort();
+        assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+    }
+}
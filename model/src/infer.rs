@@ -0,0 +1,489 @@
+// src/infer.rs
+use crate::config::DatasetConfig;
+use crate::csv;
+use crate::image_files::{self, FileFilter, ImageFile};
+use crate::model::{self, ModelTrait};
+use crate::preprocess::Preprocessor;
+use crate::registry::{self, ModelSource};
+use anyhow::{Result, anyhow};
+use burn::{prelude::*, tensor::activation::softmax};
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// `infer` の出力形式
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum OutputFormat {
+    /// 人が読む表
+    Table,
+    /// 1行 = 1画像の1候補
+    Csv,
+    /// 全画像をまとめた JSON 配列
+    Json,
+    /// 1行 = 1画像の JSON
+    Jsonl,
+}
+
+/// 上位候補の1つ
+#[derive(Debug, Clone, Serialize)]
+pub struct Prediction {
+    pub class_index: usize,
+    pub class_name: String,
+    pub probability: f32,
+}
+
+/// 1画像の上位候補
+#[derive(Debug, Clone)]
+pub struct ImagePrediction {
+    pub predictions: Vec<Prediction>,
+    /// MC Dropout 推論のときの予測エントロピー (nats)
+    pub entropy: Option<f32>,
+}
+
+/// 1画像分の推論結果 (失敗した場合は `error`)
+#[derive(Debug, Clone, Serialize)]
+pub struct InferRecord {
+    /// 指定ディレクトリからの相対パス
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub predictions: Option<Vec<Prediction>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entropy: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl InferRecord {
+    fn new(file: &ImageFile, result: Result<ImagePrediction>) -> Self {
+        let file = file.relative.clone();
+        match result {
+            Ok(prediction) => Self {
+                file,
+                predictions: Some(prediction.predictions),
+                entropy: prediction.entropy,
+                error: None,
+            },
+            Err(e) => Self {
+                file,
+                predictions: None,
+                entropy: None,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// `infer` の実行設定
+pub struct InferOptions {
+    /// 確率の高い順に出力するクラス数
+    pub top_k: usize,
+    pub format: OutputFormat,
+    pub batch: BatchOptions,
+    /// MC Dropout 推論の回数 (確率は平均し、予測エントロピーも出力する)
+    pub mc_samples: Option<usize>,
+    /// ディレクトリ指定時の走査条件
+    pub filter: FileFilter,
+}
+
+/// 画像ファイルをまとめて推論するときの設定
+#[derive(Debug, Clone, Copy)]
+pub struct BatchOptions {
+    /// 1回の forward にまとめる画像数
+    pub batch_size: usize,
+    /// 画像の読み込み・前処理に使うスレッド数
+    pub jobs: usize,
+}
+
+impl BatchOptions {
+    /// 読み込みスレッド数を省略した場合はCPU数
+    pub fn new(batch_size: usize, jobs: Option<usize>) -> Self {
+        Self {
+            batch_size,
+            jobs: jobs.unwrap_or_else(image_files::default_jobs),
+        }
+    }
+}
+
+/// ファイル、または画像を含むディレクトリを推論して `format` で出力
+pub fn infer_paths<B: Backend>(
+    config: &DatasetConfig,
+    path: &str,
+    options: &InferOptions,
+    device: &B::Device,
+) -> Result<()> {
+    let files = image_files::collect(path, &options.filter)?;
+    // モデルを読み込む前に確認する (空の表を出して正常終了しない)
+    if files.is_empty() {
+        return Err(anyhow!("条件に合う画像ファイルがありません: {path}"));
+    }
+
+    let model_path = config.get_model_path();
+    let model = registry::load_model::<B>(config, device, ModelSource::File(&model_path))?;
+
+    let results = predict_files(
+        config,
+        model.as_ref(),
+        device,
+        &files,
+        options.top_k,
+        options.batch,
+        options.mc_samples,
+    )?;
+    let records: Vec<InferRecord> = files
+        .iter()
+        .zip(results)
+        .map(|(file, result)| InferRecord::new(file, result))
+        .collect();
+    print_records(&records, options.format)
+}
+
+/// 画像ファイルを `batch_size` 枚ずつ推論し、ファイルごとの上位 `top_k` 件 (またはエラー) を返す
+///
+/// `mc_samples` を指定すると MC Dropout で推論し、平均確率と予測エントロピーを返す (Dropout のないモデルではエラー)。
+pub fn predict_files<B: Backend>(
+    config: &DatasetConfig,
+    model: &dyn ModelTrait<B>,
+    device: &B::Device,
+    files: &[ImageFile],
+    top_k: usize,
+    batch: BatchOptions,
+    mc_samples: Option<usize>,
+) -> Result<Vec<Result<ImagePrediction>>> {
+    if mc_samples.is_some() && !model.has_dropout() {
+        return Err(anyhow!(
+            "--mc-samples は Dropout のあるモデルでのみ使えます (model.type = {} には Dropout がなく、全サンプルが同じ推論になります)",
+            config.model.model_type
+        ));
+    }
+    let preprocessor = Preprocessor::new(config)?;
+    let mut results = Vec::with_capacity(files.len());
+    for chunk in files.chunks(batch.batch_size.max(1)) {
+        let pixels = image_files::load_pixels(&preprocessor, chunk, batch.jobs);
+        results.extend(predict_batch(config, model, &preprocessor, device, pixels, top_k, mc_samples));
+    }
+    Ok(results)
+}
+
+/// 読み込めた画像だけを1つのバッチにまとめて推論し、softmax 確率の上位 `top_k` 件を返す
+/// (読み込みに失敗した画像はそのエラーを返す)
+fn predict_batch<B: Backend>(
+    config: &DatasetConfig,
+    model: &dyn ModelTrait<B>,
+    preprocessor: &Preprocessor,
+    device: &B::Device,
+    pixels: Vec<Result<Vec<f32>>>,
+    top_k: usize,
+    mc_samples: Option<usize>,
+) -> Vec<Result<ImagePrediction>> {
+    let count = pixels.iter().filter(|p| p.is_ok()).count();
+    if count == 0 {
+        return pixels
+            .into_iter()
+            .map(|p| {
+                p.map(|_| ImagePrediction {
+                    predictions: Vec::new(),
+                    entropy: None,
+                })
+            })
+            .collect();
+    }
+
+    let mut batch = Vec::with_capacity(count * preprocessor.image_len());
+    for p in pixels.iter().flatten() {
+        batch.extend_from_slice(p);
+    }
+    let input = preprocessor.batch(batch, count, device);
+    let probs = match mc_samples {
+        Some(samples) => model::mc_dropout_probs(model, input, samples),
+        None => softmax(model.forward(input), 1),
+    };
+    let probs = probs
+        .into_data()
+        .to_vec::<f32>()
+        .map_err(|e| anyhow!("確率の取得に失敗: {e:?}"));
+
+    let num_classes = match &probs {
+        Ok(probs) => probs.len() / count,
+        Err(_) => 0,
+    };
+    let mut rows = 0..count;
+    pixels
+        .into_iter()
+        .map(|p| {
+            p?;
+            let row = rows.next().expect("row");
+            match &probs {
+                Ok(probs) => {
+                    let probs = &probs[row * num_classes..(row + 1) * num_classes];
+                    Ok(ImagePrediction {
+                        predictions: top_predictions(config, probs, top_k),
+                        entropy: mc_samples.map(|_| model::predictive_entropy(probs)),
+                    })
+                }
+                Err(e) => Err(anyhow!("{e}")),
+            }
+        })
+        .collect()
+}
+
+fn top_predictions(config: &DatasetConfig, probs: &[f32], top_k: usize) -> Vec<Prediction> {
+    let mut ranked: Vec<(usize, f32)> = probs.iter().copied().enumerate().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    ranked
+        .into_iter()
+        .take(top_k.max(1))
+        .map(|(class_index, probability)| Prediction {
+            class_index,
+            class_name: config
+                .class_names
+                .get(class_index)
+                .cloned()
+                .unwrap_or_else(|| format!("unknown_{}", class_index)),
+            probability,
+        })
+        .collect()
+}
+
+fn print_records(records: &[InferRecord], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Table => print_table(records),
+        OutputFormat::Csv => print_csv(records),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(records)?),
+        OutputFormat::Jsonl => {
+            for record in records {
+                println!("{}", serde_json::to_string(record)?);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_table(records: &[InferRecord]) {
+    let file_width = records
+        .iter()
+        .map(|r| r.file.len())
+        .max()
+        .unwrap_or(0)
+        .max("file".len());
+
+    // MC Dropout のときは予測エントロピーの列を足す
+    let mc = records.iter().any(|r| r.entropy.is_some());
+    let entropy_header = if mc { format!(" | {:>7}", "entropy") } else { String::new() };
+    println!(
+        "{:<file_width$} | rank | class | {:>11}{} | name",
+        "file", "probability", entropy_header
+    );
+    println!("{}", "-".repeat(file_width + 40 + entropy_header.len()));
+    for record in records {
+        match (&record.predictions, &record.error) {
+            (Some(predictions), _) => {
+                let entropy = match record.entropy {
+                    Some(entropy) => format!(" | {:>7.4}", entropy),
+                    None => String::new(),
+                };
+                for (rank, p) in predictions.iter().enumerate() {
+                    println!(
+                        "{:<file_width$} | {:>4} | {:>5} | {:>10.2}%{} | {}",
+                        record.file,
+                        rank + 1,
+                        p.class_index,
+                        p.probability * 100.0,
+                        entropy,
+                        p.class_name
+                    );
+                }
+            }
+            (None, error) => println!(
+                "{:<file_width$} | ERROR: {}",
+                record.file,
+                error.as_deref().unwrap_or("")
+            ),
+        }
+    }
+}
+
+fn print_csv(records: &[InferRecord]) {
+    let mc = records.iter().any(|r| r.entropy.is_some());
+    let mut header = vec!["file", "rank", "class_index", "class_name", "probability"];
+    if mc {
+        header.push("entropy");
+    }
+    header.push("error");
+    println!("{}", csv::row(&header));
+
+    for record in records {
+        match (&record.predictions, &record.error) {
+            (Some(predictions), _) => {
+                for (rank, p) in predictions.iter().enumerate() {
+                    let mut row = vec![
+                        record.file.clone(),
+                        (rank + 1).to_string(),
+                        p.class_index.to_string(),
+                        p.class_name.clone(),
+                        format!("{:.6}", p.probability),
+                    ];
+                    if mc {
+                        row.push(record.entropy.map_or_else(String::new, |e| format!("{:.6}", e)));
+                    }
+                    row.push(String::new());
+                    println!("{}", csv::row(&row));
+                }
+            }
+            (None, error) => {
+                let mut row = vec![record.file.clone(), String::new(), String::new(), String::new(), String::new()];
+                if mc {
+                    row.push(String::new());
+                }
+                row.push(error.clone().unwrap_or_default());
+                println!("{}", csv::row(&row));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::path::PathBuf;
+
+    fn config(class_names: &[&str]) -> DatasetConfig {
+        let mut config: DatasetConfig = serde_json::from_str(include_str!("../configs/mnist.json")).expect("config");
+        config.class_names = class_names.iter().map(|s| s.to_string()).collect();
+        config.num_classes = class_names.len();
+        config
+    }
+
+    fn ranked(predictions: &[Prediction]) -> Vec<(usize, &str)> {
+        predictions.iter().map(|p| (p.class_index, p.class_name.as_str())).collect()
+    }
+
+    #[test]
+    fn top_predictions_rank_by_probability_then_index() {
+        let config = config(&["a", "b", "c", "d", "e"]);
+        let probs = [0.1, 0.3, 0.1, 0.3, 0.2];
+
+        // 同じ確率なら番号の小さいクラスが先
+        let top = top_predictions(&config, &probs, 3);
+        assert_eq!(ranked(&top), [(1, "b"), (3, "d"), (4, "e")]);
+        assert_eq!(top[0].probability, 0.3);
+
+        // クラス数より大きい top_k は全クラス、0 は 1 件
+        let all = top_predictions(&config, &probs, 10);
+        assert_eq!(ranked(&all), [(1, "b"), (3, "d"), (4, "e"), (0, "a"), (2, "c")]);
+        assert_eq!(ranked(&top_predictions(&config, &probs, 0)), [(1, "b")]);
+
+        // class_names より出力が多いモデルは unknown_N
+        let top = top_predictions(&config, &[0.0, 0.0, 0.0, 0.0, 0.0, 1.0], 1);
+        assert_eq!(ranked(&top), [(5, "unknown_5")]);
+    }
+
+    #[test]
+    fn records_serialize_predictions_or_error() {
+        let file = ImageFile {
+            path: PathBuf::from("dir/cat/1.png"),
+            relative: "cat/1.png".to_string(),
+        };
+        let prediction = |entropy| ImagePrediction {
+            predictions: vec![Prediction {
+                class_index: 2,
+                class_name: "cat".to_string(),
+                probability: 0.5,
+            }],
+            entropy,
+        };
+
+        let ok = serde_json::to_value(InferRecord::new(&file, Ok(prediction(None)))).unwrap();
+        assert_eq!(
+            ok,
+            json!({
+                "file": "cat/1.png",
+                "predictions": [{"class_index": 2, "class_name": "cat", "probability": 0.5}]
+            })
+        );
+
+        let mc = serde_json::to_value(InferRecord::new(&file, Ok(prediction(Some(0.25))))).unwrap();
+        assert_eq!(mc["entropy"], json!(0.25));
+        assert!(mc.get("error").is_none());
+
+        let err = serde_json::to_value(InferRecord::new(&file, Err(anyhow!("壊れた画像")))).unwrap();
+        assert_eq!(err, json!({"file": "cat/1.png", "error": "壊れた画像"}));
+    }
+
+    /// 画素の平均 m から logits [m, 0, -m] を返し、forward ごとのバッチ数を記録するモデル
+    #[cfg(feature = "ndarray")]
+    struct Probe {
+        batches: std::cell::RefCell<Vec<usize>>,
+    }
+
+    #[cfg(feature = "ndarray")]
+    impl<B: Backend> ModelTrait<B> for Probe {
+        fn forward_mode(&self, x: Tensor<B, 4>, _mode: model::ForwardMode) -> Tensor<B, 2> {
+            let [batch, ..] = x.dims();
+            self.batches.borrow_mut().push(batch);
+            let m = x.reshape([batch as i32, -1]).mean_dim(1);
+            Tensor::cat(vec![m.clone(), m.zeros_like(), m.neg()], 1)
+        }
+    }
+
+    #[cfg(feature = "ndarray")]
+    #[test]
+    fn predict_files_maps_batched_results_back_to_each_file() {
+        use burn::backend::NdArray;
+        use image::{GrayImage, Luma};
+
+        let dir = std::env::temp_dir().join(format!("infer-batch-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = |name: &str| ImageFile {
+            path: dir.join(name),
+            relative: name.to_string(),
+        };
+        for (name, value) in [("dark.png", 30u8), ("mid.png", 120), ("bright.png", 220)] {
+            GrayImage::from_pixel(8, 8, Luma([value])).save(dir.join(name)).unwrap();
+        }
+        // PNG として判定されるがデコードできない画像と、画像でないファイル
+        std::fs::write(dir.join("truncated.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+
+        // batch_size 2: [missing, truncated] は全て失敗, [dark, mid], [notes, bright]
+        let files: Vec<ImageFile> = ["missing.png", "truncated.png", "dark.png", "mid.png", "notes.txt", "bright.png"]
+            .into_iter()
+            .map(file)
+            .collect();
+        let config = config(&["bright", "none", "dark"]);
+        let device = Default::default();
+        let probe = Probe {
+            batches: Default::default(),
+        };
+        let predict = |files: &[ImageFile], batch_size| {
+            let batch = BatchOptions { batch_size, jobs: 2 };
+            predict_files::<NdArray>(&config, &probe, &device, files, 3, batch, None).unwrap()
+        };
+        let results = predict(&files, 2);
+
+        // 全て読み込めなかったバッチは forward しない
+        assert_eq!(*probe.batches.borrow(), [2, 1]);
+        assert_eq!(results.len(), files.len());
+        let error = |i: usize| results[i].as_ref().err().map(|e| e.to_string()).unwrap_or_default();
+        assert!(error(0).contains("対応する画像形式"), "{}", error(0));
+        assert!(!error(1).is_empty() && !error(1).contains("対応する画像形式"), "{}", error(1));
+        assert!(error(4).contains("対応する画像形式"), "{}", error(4));
+
+        // 各ファイルの結果は1枚ずつ推論した結果と同じで、明るいほどクラス 0 の確率が高い
+        let top = |i: usize| {
+            let prediction = results[i].as_ref().expect("prediction");
+            assert_eq!(prediction.predictions.len(), 3);
+            prediction.predictions.iter().find(|p| p.class_index == 0).unwrap().probability
+        };
+        for i in [2, 3, 5] {
+            let single = predict(&files[i..=i], 1).remove(0).unwrap();
+            let expected = single.predictions.iter().find(|p| p.class_index == 0).unwrap().probability;
+            assert!((top(i) - expected).abs() < 1e-6, "{}", files[i].relative);
+        }
+        assert!(top(2) < top(3) && top(3) < top(5));
+        assert_eq!(results[2].as_ref().unwrap().predictions[0].class_name, "dark");
+        assert_eq!(results[5].as_ref().unwrap().predictions[0].class_name, "bright");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
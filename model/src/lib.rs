@@ -1,11 +1,36 @@
+#![recursion_limit = "256"]
 // no_std を外して serde_json を wasm でも利用可能にする
 extern crate alloc;
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod augment; // training-time data augmentation (non-wasm)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod checkpoint; // checkpoint save / resume (non-wasm)
 pub mod config; // 設定ファイル管理 (both wasm and native)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod csv; // CSV rows for infer / report output (non-wasm)
 
 #[cfg(not(target_arch = "wasm32"))]
 pub mod data; // training / dataset logic (non-wasm)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod eval; // evaluation on the test split (non-wasm)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod idx; // IDX file reader for MNIST-family datasets (non-wasm)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod image_files; // image file discovery and format sniffing (non-wasm)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod image_folder; // root/<class>/ image-folder dataset (non-wasm)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod infer; // CLI inference on image files (non-wasm)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod lr_schedule; // learning-rate schedules (non-wasm)
+#[cfg(not(target_arch = "wasm32"))]
+pub mod metrics; // metrics.jsonl log and run report (non-wasm)
 pub mod model;
+pub mod preprocess; // pixels -> normalized tensor (both wasm and native)
+pub mod registry; // model.type -> model (both wasm and native)
+pub mod sequential; // model.layers -> model (both wasm and native)
+pub mod summary; // per-layer shapes, params and FLOPs (both wasm and native)
 #[cfg(not(target_arch = "wasm32"))]
 pub mod train; // training entry (non-wasm)
 
@@ -34,4 +59,4 @@ pub mod web; // wasm entry points (only compile for wasm)
 
 // Re-export commonly used types for web
 pub use config::DatasetConfig;
-pub use model::{CifarNet, LeNet, ModelTrait};
+pub use model::{CifarNet, LeNet, ModelTrait, ResNet};
@@ -0,0 +1,141 @@
+// src/lr_schedule.rs
+use crate::config::{LrSchedule, LrSchedulerConfig};
+use std::f64::consts::PI;
+
+/// 学習率をグローバルステップから計算する
+///
+/// 状態を持たないので、チェックポイントから再開しても同じ学習率の列になる。
+pub struct LrScheduler {
+    base_lr: f64,
+    config: LrSchedulerConfig,
+    steps_per_epoch: usize,
+    total_steps: usize,
+    warmup_steps: usize,
+}
+
+impl LrScheduler {
+    pub fn new(base_lr: f64, config: &LrSchedulerConfig, epochs: u32, steps_per_epoch: usize) -> Self {
+        let steps_per_epoch = steps_per_epoch.max(1);
+        Self {
+            base_lr,
+            config: config.clone(),
+            steps_per_epoch,
+            total_steps: (epochs as usize * steps_per_epoch).max(1),
+            warmup_steps: (config.warmup_epochs.max(0.0) * steps_per_epoch as f64).round() as usize,
+        }
+    }
+
+    /// `step` 番目 (0始まり) の更新で使う学習率
+    pub fn lr(&self, step: usize) -> f64 {
+        let lr = self.scheduled(step);
+        if step < self.warmup_steps {
+            lr * (step + 1) as f64 / self.warmup_steps as f64
+        } else {
+            lr
+        }
+    }
+
+    fn scheduled(&self, step: usize) -> f64 {
+        match self.config.schedule {
+            LrSchedule::Constant => self.base_lr,
+            LrSchedule::Step { step_size, gamma } => {
+                let epoch = step / self.steps_per_epoch;
+                self.base_lr * gamma.powi((epoch / step_size.max(1) as usize) as i32)
+            }
+            LrSchedule::Cosine { min_lr } => {
+                let span = self.total_steps.saturating_sub(self.warmup_steps).max(1);
+                let t = step.saturating_sub(self.warmup_steps) as f64 / span as f64;
+                anneal(self.base_lr, min_lr, t)
+            }
+            LrSchedule::OneCycle {
+                pct_start,
+                div_factor,
+                final_div_factor,
+            } => {
+                let initial = self.base_lr / div_factor;
+                let last = initial / final_div_factor;
+                let peak = (pct_start.clamp(0.0, 1.0) * self.total_steps as f64).max(1.0);
+                let step = step as f64;
+                if step < peak {
+                    anneal(initial, self.base_lr, step / peak)
+                } else {
+                    let span = (self.total_steps as f64 - peak).max(1.0);
+                    anneal(self.base_lr, last, (step - peak) / span)
+                }
+            }
+        }
+    }
+}
+
+/// `t` (0..1) に応じて `from` から `to` へコサインで変化させる
+fn anneal(from: f64, to: f64, t: f64) -> f64 {
+    let t = t.clamp(0.0, 1.0);
+    to + (from - to) * 0.5 * (1.0 + (PI * t).cos())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scheduler(schedule: LrSchedule, warmup_epochs: f64, epochs: u32, steps_per_epoch: usize) -> LrScheduler {
+        let config = LrSchedulerConfig {
+            schedule,
+            warmup_epochs,
+        };
+        LrScheduler::new(0.1, &config, epochs, steps_per_epoch)
+    }
+
+    fn assert_lr(scheduler: &LrScheduler, expected: &[(usize, f64)]) {
+        for &(step, lr) in expected {
+            let actual = scheduler.lr(step);
+            assert!((actual - lr).abs() < 1e-12, "step {step}: {actual} != {lr}");
+        }
+    }
+
+    #[test]
+    fn warmup_ramps_linearly_to_the_schedule() {
+        let s = scheduler(LrSchedule::Constant, 1.0, 3, 4);
+        assert_lr(&s, &[(0, 0.025), (1, 0.05), (3, 0.1), (4, 0.1), (11, 0.1)]);
+    }
+
+    #[test]
+    fn step_decays_every_step_size_epochs() {
+        let s = scheduler(LrSchedule::Step { step_size: 2, gamma: 0.1 }, 0.0, 6, 10);
+        assert_lr(&s, &[(0, 0.1), (19, 0.1), (20, 0.1 * 0.1), (39, 0.1 * 0.1), (40, 0.1 * 0.1 * 0.1)]);
+    }
+
+    #[test]
+    fn cosine_anneals_after_warmup() {
+        let s = scheduler(LrSchedule::Cosine { min_lr: 0.0 }, 0.0, 2, 5);
+        assert_lr(&s, &[(0, 0.1), (5, 0.05), (10, 0.0), (25, 0.0)]);
+
+        // ウォームアップ (5 ステップ) の後の 5 ステップで下げる
+        let s = scheduler(LrSchedule::Cosine { min_lr: 0.01 }, 1.0, 2, 5);
+        let at = |t: f64| 0.01 + 0.09 * 0.5 * (1.0 + (PI * t).cos());
+        assert_lr(&s, &[(0, 0.1 / 5.0), (4, 0.1), (5, 0.1), (7, at(0.4)), (10, 0.01)]);
+    }
+
+    #[test]
+    fn one_cycle_rises_to_base_lr_then_falls() {
+        let schedule = LrSchedule::OneCycle {
+            pct_start: 0.3,
+            div_factor: 25.0,
+            final_div_factor: 1e4,
+        };
+        let s = scheduler(schedule, 0.0, 1, 10);
+        assert_lr(&s, &[(0, 0.1 / 25.0), (3, 0.1), (10, 0.1 / 25.0 / 1e4)]);
+    }
+
+    #[test]
+    fn zero_steps_and_resume_are_well_defined() {
+        // エポック数・ステップ数が 0 でも 0 除算にならない
+        let s = scheduler(LrSchedule::Cosine { min_lr: 0.0 }, 0.5, 0, 0);
+        assert_lr(&s, &[(0, 0.1), (1, 0.1), (2, 0.0), (100, 0.0)]);
+
+        // 状態を持たないので、ステップ 17 から再開しても中断しない場合と同じ値
+        // (ウォームアップ 10 ステップの後、残り 20 ステップの 7 ステップ目)
+        let resumed = scheduler(LrSchedule::Cosine { min_lr: 0.001 }, 1.0, 3, 10);
+        let at = |t: f64| 0.001 + 0.099 * 0.5 * (1.0 + (PI * t).cos());
+        assert_lr(&resumed, &[(17, at(0.35)), (18, at(0.4)), (29, at(0.95))]);
+    }
+}
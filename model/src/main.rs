@@ -1,24 +1,54 @@
 #![recursion_limit = "256"]
 // src/main.rs
+mod augment;
+mod checkpoint;
 mod config;
+mod csv;
 mod data;
+mod eval;
+mod idx;
+mod image_files;
+mod image_folder;
+mod infer;
+mod lr_schedule;
+mod metrics;
 mod model;
+mod preprocess;
+mod registry;
+mod sequential;
+mod summary;
 mod train;
 
 use anyhow::{Result, anyhow};
-use burn::{prelude::*, record::CompactRecorder};
+use burn::prelude::*;
 use burn_wgpu::{Wgpu, WgpuDevice};
-use clap::{Args, Parser, Subcommand};
-use image::{DynamicImage, ImageReader};
-use std::{fs, path::Path};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use config::DatasetConfig;
-use model::{LeNet, CifarNet, ModelTrait};
 
 #[derive(Subcommand)]
 enum Commands {
     Train(TrainArgs),
     Eval(EvalArgs),
     Infer(InferArgs),
+    /// 学習ログ (metrics.jsonl) を比較表にまとめる
+    Report(ReportArgs),
+    /// モデルの各層の出力の形・パラメータ数・FLOPs を表示する
+    Summary(SummaryArgs),
+    /// 設定ファイルの操作
+    #[command(subcommand)]
+    Config(ConfigCommands),
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// 設定ファイルを読み込み、項目どうしの矛盾がないか確認する
+    Check(ConfigCheckArgs),
+}
+
+#[derive(Args)]
+struct ConfigCheckArgs {
+    /// 確認する設定の名前 (省略時は configs/ のすべて)
+    datasets: Vec<String>,
 }
 
 #[derive(Args)]
@@ -29,12 +59,24 @@ struct TrainArgs {
     epochs: Option<u32>,
     #[arg(short, long)]
     batch_size: Option<usize>,
+    /// チェックポイントから再開 (パス省略時は最新)
+    #[arg(long, num_args = 0..=1, default_missing_value = "latest")]
+    resume: Option<String>,
+    /// データセットの代わりに合成データを使う (動作確認用, CIFAR-10 のみ)
+    #[arg(long)]
+    synthetic: bool,
 }
 
 #[derive(Args)]
 struct EvalArgs {
     #[arg(short, long, required = true)]
     dataset: String,
+    /// テストデータの代わりに `<DIR>/<クラス名>/` 以下の画像ファイル (PNG/JPEG/BMP/GIF/WebP/TIFF) で評価する
+    #[arg(long, value_name = "DIR")]
+    folder: Option<String>,
+    /// テストデータの代わりに合成データを使う (動作確認用, CIFAR-10 のみ)
+    #[arg(long, conflicts_with = "folder")]
+    synthetic: bool,
 }
 
 #[derive(Args)]
@@ -43,186 +85,188 @@ struct InferArgs {
     dataset: String,
     #[arg(short, long, required = true)]
     path: String,
+    /// 確率の高い順に表示するクラス数
+    #[arg(long, default_value_t = 1)]
+    top_k: usize,
+    #[arg(long, value_enum, default_value = "table")]
+    format: infer::OutputFormat,
+    /// 1回の推論にまとめる画像数
+    #[arg(long, default_value_t = 64)]
+    batch_size: usize,
+    /// 画像の読み込み・前処理に使うスレッド数 (省略時はCPU数)
+    #[arg(long)]
+    jobs: Option<usize>,
+    /// Dropout を有効にしたまま N 回推論し、平均確率と予測エントロピーを出す (MC Dropout, Dropout のあるモデルのみ)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    mc_samples: Option<u32>,
+    /// サブディレクトリも走査する
+    #[arg(short, long)]
+    recursive: bool,
+    /// 対象にするファイルの glob パターン (複数指定可, '/' を含まなければファイル名と照合, `*` は '/' に一致しない)
+    #[arg(long)]
+    glob: Vec<String>,
+    /// 除外するファイルのパターン (複数指定可)
+    #[arg(long)]
+    exclude: Vec<String>,
+}
+
+#[derive(Args)]
+struct SummaryArgs {
+    #[arg(short, long, required = true)]
+    dataset: String,
+}
+
+#[derive(Args)]
+struct ReportArgs {
+    /// metrics.jsonl またはそれを含むアーティファクトディレクトリ
+    #[arg(required = true)]
+    paths: Vec<String>,
+    #[arg(long, value_enum, default_value = "table")]
+    format: ReportFormat,
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ReportFormat {
+    Table,
+    Csv,
+}
+
+/// 学習・評価・推論に使うBurnバックエンド
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum BackendKind {
+    /// GPU (wgpu)
+    Wgpu,
+    /// CPU (ndarray) - `ndarray` feature が必要
+    Ndarray,
+}
+
+impl Default for BackendKind {
+    /// `ndarray` feature があれば CPU (GPU のない環境でも動く)、なければ wgpu
+    fn default() -> Self {
+        if cfg!(feature = "ndarray") {
+            BackendKind::Ndarray
+        } else {
+            BackendKind::Wgpu
+        }
+    }
 }
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
-struct CLI {
+struct Cli {
+    #[arg(long, value_enum, global = true, default_value_t)]
+    backend: BackendKind,
     #[command(subcommand)]
     command: Commands,
 }
 
 fn main() -> Result<()> {
-    let cli = CLI::parse();
+    let cli = Cli::parse();
+    // レポート・設定の確認はバックエンドを使わない
     match &cli.command {
+        Commands::Report(args) => return report(args),
+        Commands::Config(ConfigCommands::Check(args)) => return check_configs(args),
+        _ => {}
+    }
+    match cli.backend {
+        BackendKind::Wgpu => run::<Wgpu>(&cli.command, WgpuDevice::default()),
+        #[cfg(feature = "ndarray")]
+        BackendKind::Ndarray => run::<burn::backend::NdArray>(
+            &cli.command,
+            burn::backend::ndarray::NdArrayDevice::Cpu,
+        ),
+        #[cfg(not(feature = "ndarray"))]
+        BackendKind::Ndarray => Err(anyhow!(
+            "ndarray バックエンドは無効です (--features ndarray でビルドしてください)"
+        )),
+    }
+}
+
+fn run<B: Backend>(command: &Commands, device: B::Device) -> Result<()> {
+    match command {
         Commands::Train(args) => {
-            let config = DatasetConfig::load(&args.dataset)?;
+            let mut config = DatasetConfig::load(&args.dataset)?;
+            if args.synthetic {
+                config.use_synthetic()?;
+            }
             let train_config = train::TrainConfig {
                 dataset_config: config.clone(),
                 epochs: args.epochs.unwrap_or(config.training.epochs),
                 batch_size: args.batch_size.unwrap_or(config.training.batch_size),
+                resume: args.resume.clone(),
             };
-            train::train(train_config)?;
+            train::train::<B>(train_config, device)?;
         }
         Commands::Eval(args) => {
-            let _config = DatasetConfig::load(&args.dataset)?;
-            println!("(tip) 現状は学習ログの test_acc を参照してください。");
+            let mut config = DatasetConfig::load(&args.dataset)?;
+            if args.synthetic {
+                config.use_synthetic()?;
+            }
+            let report = match &args.folder {
+                Some(dir) => eval::evaluate_folder::<B>(&config, dir, &device)?,
+                None => eval::evaluate::<B>(&config, &device)?,
+            };
+            eval::print_report(&report);
+            let report_path = eval::save_report(&config, &report)?;
+            println!("Saved: {}", report_path);
         }
         Commands::Infer(args) => {
             let config = DatasetConfig::load(&args.dataset)?;
-            infer_paths(&config, &args.path)?;
+            let options = infer::InferOptions {
+                top_k: args.top_k,
+                format: args.format,
+                batch: infer::BatchOptions::new(args.batch_size, args.jobs),
+                mc_samples: args.mc_samples.map(|n| n as usize),
+                filter: image_files::FileFilter::new(args.recursive, &args.glob, &args.exclude)?,
+            };
+            infer::infer_paths::<B>(&config, &args.path, &options, &device)?;
+        }
+        Commands::Report(args) => report(args)?,
+        Commands::Config(ConfigCommands::Check(args)) => check_configs(args)?,
+        Commands::Summary(args) => {
+            let config = DatasetConfig::load(&args.dataset)?;
+            let (layers, num_params) = registry::summarize::<B>(&config, &device)?;
+            summary::print_summary(&config, &layers, num_params);
+            summary::print_artifacts(&config);
         }
     }
     Ok(())
 }
 
-fn infer_paths(config: &DatasetConfig, path: &str) -> Result<()> {
-    type B = Wgpu;
-    let device = WgpuDevice::default();
-
-    let model_path = config.get_model_path();
-    
-    // モデルタイプに応じて適切なモデルを作成
-    let (model_mnist, model_cifar): (Option<LeNet<B>>, Option<CifarNet<B>>) = match config.model.model_type.as_str() {
-        "lenet" => {
-            let model = LeNet::<B>::new(&device, config)
-                .load_file(&model_path, &CompactRecorder::new(), &device)
-                .map_err(|e| anyhow!("モデル読み込み失敗: {e}"))?;
-            (Some(model), None)
-        },
-        "cifar_net" => {
-            let model = CifarNet::<B>::new(&device, config)
-                .load_file(&model_path, &CompactRecorder::new(), &device)
-                .map_err(|e| anyhow!("モデル読み込み失敗: {e}"))?;
-            (None, Some(model))
-        },
-        _ => return Err(anyhow!("未対応のモデルタイプ: {}", config.model.model_type))
-    };
-
-    let meta = fs::metadata(path)?;
-    if meta.is_dir() {
-        let mut files: Vec<_> = fs::read_dir(path)?
-            .filter_map(|e| e.ok())
-            .map(|e| e.path())
-            .filter(|p| {
-                p.extension()
-                    .map(|ext| ext.eq_ignore_ascii_case("png"))
-                    .unwrap_or(false)
-            })
-            .collect();
-        files.sort();
-        if files.is_empty() {
-            println!("(info) ディレクトリ内に .png ファイルがありません: {path}");
-            return Ok(());
-        }
-        println!("File,Pred,Class");
-        for p in files {
-            match infer_single_path(config, &model_mnist, &model_cifar, &device, &p) {
-                Ok((pred_idx, class_name)) => println!("{},{},{}", p.display(), pred_idx, class_name),
-                Err(e) => eprintln!("{},ERROR:{e},", p.display()),
-            }
-        }
-    } else if Path::new(path).is_file() {
-        let (pred_idx, class_name) = infer_single_path(config, &model_mnist, &model_cifar, &device, Path::new(path))?;
-        println!("Predicted: {} ({})", pred_idx, class_name);
-    } else {
-        return Err(anyhow!(
-            "指定パスがファイルでもディレクトリでもありません: {path}"
-        ));
+fn report(args: &ReportArgs) -> Result<()> {
+    let summaries = args
+        .paths
+        .iter()
+        .map(|path| metrics::summarize(path))
+        .collect::<Result<Vec<_>>>()?;
+    match args.format {
+        ReportFormat::Table => metrics::print_table(&summaries),
+        ReportFormat::Csv => metrics::print_csv(&summaries),
     }
     Ok(())
 }
 
-fn infer_single_path<B: Backend>(
-    config: &DatasetConfig,
-    model_mnist: &Option<LeNet<B>>,
-    model_cifar: &Option<CifarNet<B>>,
-    device: &<B as Backend>::Device,
-    path: &Path,
-) -> Result<(i32, String)> {
-    let img = ImageReader::open(path)?.decode()?;
-    let tensor = to_tensor::<B>(&img, config, device);
-    
-    let logits = match config.model.model_type.as_str() {
-        "lenet" => {
-            let model = model_mnist.as_ref().ok_or_else(|| anyhow!("LeNetモデルが初期化されていません"))?;
-            model.forward(tensor)
-        },
-        "cifar_net" => {
-            let model = model_cifar.as_ref().ok_or_else(|| anyhow!("CifarNetモデルが初期化されていません"))?;
-            model.forward(tensor)
-        },
-        _ => return Err(anyhow!("未対応のモデルタイプ: {}", config.model.model_type))
+fn check_configs(args: &ConfigCheckArgs) -> Result<()> {
+    let names = if args.datasets.is_empty() {
+        DatasetConfig::available()?
+    } else {
+        args.datasets.clone()
     };
-    
-    let pred_idx = logits
-        .argmax(1)
-        .into_data()
-        .to_vec::<i32>()
-        .expect("prediction vec")[0];
-    
-    let class_name = config.class_names.get(pred_idx as usize)
-        .cloned()
-        .unwrap_or_else(|| format!("unknown_{}", pred_idx));
-    
-    Ok((pred_idx, class_name))
-}
-
-fn to_tensor<B: Backend>(img: &DynamicImage, config: &DatasetConfig, device: &B::Device) -> Tensor<B, 4> {
-    let [height, width] = config.input_size;
-    
-    match config.input_channels {
-        1 => {
-            // グレースケール (MNIST)
-            let img = img.to_luma8();
-            let img = image::imageops::resize(&img, width as u32, height as u32, image::imageops::FilterType::Nearest);
-            
-            let mean = match &config.training.normalization.mean {
-                config::NormalizationValue::Single(v) => *v,
-                _ => panic!("グレースケール画像には単一の平均値が必要です")
-            };
-            let std = match &config.training.normalization.std {
-                config::NormalizationValue::Single(v) => *v,
-                _ => panic!("グレースケール画像には単一の標準偏差が必要です")
-            };
-            
-            let data: Vec<f32> = img
-                .pixels()
-                .map(|p| (p[0] as f32 / 255.0 - mean) / std)
-                .collect();
-            let t = Tensor::<B, 1>::from_floats(data.as_slice(), device)
-                .reshape([1, height, width]); // [C,H,W]
-            t.reshape([1, 1, height, width]) // [B,C,H,W]
-        },
-        3 => {
-            // RGB (CIFAR-10)
-            let img = img.to_rgb8();
-            let img = image::imageops::resize(&img, width as u32, height as u32, image::imageops::FilterType::Nearest);
-            
-            let mean = match &config.training.normalization.mean {
-                config::NormalizationValue::Triple(v) => *v,
-                _ => panic!("RGB画像には3つの平均値が必要です")
-            };
-            let std = match &config.training.normalization.std {
-                config::NormalizationValue::Triple(v) => *v,
-                _ => panic!("RGB画像には3つの標準偏差が必要です")
-            };
-            
-            let mut data = Vec::with_capacity(3 * height * width);
-            let pixels: Vec<_> = img.pixels().collect();
-            
-            // Channelごとに分離 (CHW format)
-            for c in 0..3 {
-                for pixel in &pixels {
-                    let value = (pixel[c] as f32 / 255.0 - mean[c]) / std[c];
-                    data.push(value);
-                }
+    let mut failed = 0;
+    for name in &names {
+        match DatasetConfig::load(name) {
+            Ok(config) => println!(
+                "OK  {}: {} {:?}x{}ch -> {} classes",
+                name, config.model.model_type, config.input_size, config.input_channels, config.num_classes
+            ),
+            Err(e) => {
+                failed += 1;
+                println!("NG  {}: {}", name, e);
             }
-            
-            let t = Tensor::<B, 1>::from_floats(data.as_slice(), device)
-                .reshape([3, height, width]); // [C,H,W]
-            t.reshape([1, 3, height, width]) // [B,C,H,W]
-        },
-        _ => panic!("未対応のチャンネル数: {}", config.input_channels)
+        }
+    }
+    if failed > 0 {
+        return Err(anyhow!("{} 個中 {} 個の設定に問題があります", names.len(), failed));
     }
+    Ok(())
 }
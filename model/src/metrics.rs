@@ -0,0 +1,457 @@
+// src/metrics.rs
+use crate::config::{DatasetConfig, LrSchedulerConfig, METRICS_FILE, OptimizerConfig};
+use crate::csv;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{LineWriter, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+/// `metrics.jsonl` の1行
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MetricsRecord {
+    /// 実行開始 (再開時にも書き込む)
+    Run {
+        dataset: String,
+        model: String,
+        optimizer: OptimizerConfig,
+        lr_scheduler: LrSchedulerConfig,
+        epochs: u32,
+        batch_size: usize,
+        learning_rate: f64,
+        /// 再開した場合のグローバルステップ
+        resumed_from: Option<usize>,
+    },
+    Step {
+        epoch: u32,
+        step: usize,
+        loss: f32,
+        lr: f64,
+        images_per_sec: f64,
+        elapsed_secs: f64,
+    },
+    Epoch {
+        epoch: u32,
+        step: usize,
+        train_loss: f32,
+        val_loss: f32,
+        val_acc: f32,
+        lr: f64,
+        /// 学習部分のみ (評価時間を除く)
+        images_per_sec: f64,
+        elapsed_secs: f64,
+    },
+    /// 学習終了後、最良エポックのモデルでのテスト結果
+    Test {
+        best_epoch: u32,
+        test_loss: f32,
+        test_acc: f32,
+        samples: usize,
+    },
+}
+
+impl MetricsRecord {
+    /// ステップ・エポックの記録ならそのグローバルステップ
+    fn step(&self) -> Option<usize> {
+        match self {
+            MetricsRecord::Run { .. } | MetricsRecord::Test { .. } => None,
+            MetricsRecord::Step { step, .. } | MetricsRecord::Epoch { step, .. } => Some(*step),
+        }
+    }
+}
+
+/// `artifacts.dir/metrics.jsonl` に学習の記録を1行ずつ追記する
+pub struct MetricsLogger {
+    writer: LineWriter<File>,
+    start: Instant,
+    /// 再開前の実行の経過時間
+    elapsed_offset: f64,
+}
+
+impl MetricsLogger {
+    /// 新規学習ならログを作り直し、再開なら `resume_step` までの記録を残して追記する
+    ///
+    /// 再開点より後 (放棄した実行の続き・そのテスト結果) は捨てる。
+    /// 残す記録は一時ファイルに書いてから置き換えるので、途中で失敗しても元のログは失われない。
+    pub fn create(config: &DatasetConfig, resume_step: Option<usize>, elapsed_offset: f64) -> Result<Self> {
+        let path = config.get_metrics_path();
+        let file = match resume_step {
+            Some(step) if Path::new(&path).is_file() => {
+                let records = read_records(Path::new(&path))?;
+                let end = records
+                    .iter()
+                    .rposition(|r| r.step().is_some_and(|s| s <= step))
+                    .map_or(0, |i| i + 1);
+
+                let tmp_path = format!("{}.tmp", path);
+                let mut tmp = File::create(&tmp_path)?;
+                for record in &records[..end] {
+                    writeln!(tmp, "{}", serde_json::to_string(record)?)?;
+                }
+                tmp.sync_all()?;
+                drop(tmp);
+                fs::rename(&tmp_path, &path).map_err(|e| anyhow!("学習ログの更新失敗 {}: {}", path, e))?;
+                OpenOptions::new().append(true).open(&path)?
+            }
+            _ => File::create(&path)?,
+        };
+
+        Ok(Self {
+            writer: LineWriter::new(file),
+            start: Instant::now(),
+            elapsed_offset,
+        })
+    }
+
+    /// 学習開始からの経過時間 (再開前の実行分を含む)
+    pub fn elapsed_secs(&self) -> f64 {
+        self.elapsed_offset + self.start.elapsed().as_secs_f64()
+    }
+
+    pub fn log(&mut self, record: &MetricsRecord) -> Result<()> {
+        let line = serde_json::to_string(record)?;
+        writeln!(self.writer, "{line}").map_err(|e| anyhow!("学習ログの書き込み失敗: {e}"))
+    }
+}
+
+fn read_records(path: &Path) -> Result<Vec<MetricsRecord>> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| anyhow!("学習ログ読み込み失敗 {}: {}", path.display(), e))?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line)
+                .map_err(|e| anyhow!("学習ログ解析失敗 {}:{}: {}", path.display(), i + 1, e))
+        })
+        .collect()
+}
+
+/// 1回の学習 (`metrics.jsonl` 1つ) の要約
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    pub path: String,
+    pub dataset: String,
+    pub model: String,
+    pub optimizer: String,
+    pub lr_scheduler: String,
+    pub batch_size: usize,
+    /// 完了したエポック数 (早期終了を含む)
+    pub epochs: usize,
+    pub best_epoch: u32,
+    /// 最良エポックの検証結果
+    pub val_acc: f32,
+    pub val_loss: f32,
+    /// 学習が最後まで終わっていなければ None
+    pub test_acc: Option<f32>,
+    pub test_loss: Option<f32>,
+    pub final_train_loss: f32,
+    pub images_per_sec: f64,
+    pub elapsed_secs: f64,
+}
+
+/// `metrics.jsonl` の1エポック分
+struct EpochRow {
+    epoch: u32,
+    train_loss: f32,
+    val_loss: f32,
+    val_acc: f32,
+    images_per_sec: f64,
+    elapsed_secs: f64,
+}
+
+/// `metrics.jsonl` (またはそれを含むアーティファクトディレクトリ) を要約
+pub fn summarize(path: &str) -> Result<RunSummary> {
+    let mut file = PathBuf::from(path);
+    if file.is_dir() {
+        file.push(METRICS_FILE);
+    }
+    let records = read_records(&file)?;
+
+    let Some(MetricsRecord::Run {
+        dataset,
+        model,
+        optimizer,
+        lr_scheduler,
+        batch_size,
+        ..
+    }) = records.first().cloned()
+    else {
+        return Err(anyhow!("学習ログの先頭に実行情報がありません: {}", file.display()));
+    };
+
+    let epochs: Vec<EpochRow> = records
+        .iter()
+        .filter_map(|r| match *r {
+            MetricsRecord::Epoch {
+                epoch,
+                train_loss,
+                val_loss,
+                val_acc,
+                images_per_sec,
+                elapsed_secs,
+                ..
+            } => Some(EpochRow {
+                epoch,
+                train_loss,
+                val_loss,
+                val_acc,
+                images_per_sec,
+                elapsed_secs,
+            }),
+            _ => None,
+        })
+        .collect();
+    let Some(last) = epochs.last() else {
+        return Err(anyhow!("完了したエポックがありません: {}", file.display()));
+    };
+    let test = records.iter().rev().find_map(|r| match *r {
+        MetricsRecord::Test {
+            best_epoch,
+            test_loss,
+            test_acc,
+            ..
+        } => Some((best_epoch, test_loss, test_acc)),
+        _ => None,
+    });
+
+    // テスト結果があればその最良エポック、なければ検証精度が最大のエポック (同率なら早い方)
+    let best = test
+        .and_then(|(best_epoch, _, _)| epochs.iter().find(|e| e.epoch == best_epoch))
+        .or_else(|| {
+            epochs
+                .iter()
+                .rev()
+                .max_by(|a, b| a.val_acc.total_cmp(&b.val_acc))
+        })
+        .expect("epochs");
+
+    Ok(RunSummary {
+        path: file.display().to_string(),
+        dataset,
+        model,
+        optimizer: optimizer.kind().to_string(),
+        lr_scheduler: lr_scheduler.schedule.kind().to_string(),
+        batch_size,
+        epochs: epochs.len(),
+        best_epoch: best.epoch,
+        val_acc: best.val_acc,
+        val_loss: best.val_loss,
+        test_acc: test.map(|t| t.2),
+        test_loss: test.map(|t| t.1),
+        final_train_loss: last.train_loss,
+        images_per_sec: epochs.iter().map(|e| e.images_per_sec).sum::<f64>() / epochs.len() as f64,
+        elapsed_secs: last.elapsed_secs,
+    })
+}
+
+const REPORT_HEADER: [&str; 14] = [
+    "run",
+    "dataset",
+    "model",
+    "optimizer",
+    "scheduler",
+    "batch",
+    "epochs",
+    "best",
+    "val_acc",
+    "val_loss",
+    "test_acc",
+    "train_loss",
+    "img/s",
+    "time",
+];
+
+fn report_row(s: &RunSummary) -> Vec<String> {
+    vec![
+        s.path.clone(),
+        s.dataset.clone(),
+        s.model.clone(),
+        s.optimizer.clone(),
+        s.lr_scheduler.clone(),
+        s.batch_size.to_string(),
+        s.epochs.to_string(),
+        s.best_epoch.to_string(),
+        format!("{:.2}%", s.val_acc * 100.0),
+        format!("{:.4}", s.val_loss),
+        s.test_acc
+            .map_or_else(|| "-".to_string(), |acc| format!("{:.2}%", acc * 100.0)),
+        format!("{:.4}", s.final_train_loss),
+        format!("{:.0}", s.images_per_sec),
+        format!("{:.0}s", s.elapsed_secs),
+    ]
+}
+
+/// 複数の学習結果を比較表として表示
+pub fn print_table(summaries: &[RunSummary]) {
+    let rows: Vec<Vec<String>> = summaries.iter().map(report_row).collect();
+    let widths: Vec<usize> = REPORT_HEADER
+        .iter()
+        .enumerate()
+        .map(|(i, h)| rows.iter().map(|r| r[i].len()).max().unwrap_or(0).max(h.len()))
+        .collect();
+
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(c, w)| format!("{c:<w$}"))
+            .collect::<Vec<_>>()
+            .join(" | ")
+            .trim_end()
+            .to_string()
+    };
+    println!("{}", line(REPORT_HEADER.to_vec()));
+    println!("{}", "-".repeat(widths.iter().sum::<usize>() + 3 * (widths.len() - 1)));
+    for row in &rows {
+        println!("{}", line(row.iter().map(String::as_str).collect()));
+    }
+}
+
+/// 複数の学習結果をCSVで表示 (テスト未実施の列は空)
+pub fn print_csv(summaries: &[RunSummary]) {
+    println!(
+        "{}",
+        csv::row(&[
+            "path",
+            "dataset",
+            "model",
+            "optimizer",
+            "lr_scheduler",
+            "batch_size",
+            "epochs",
+            "best_epoch",
+            "val_acc",
+            "val_loss",
+            "test_acc",
+            "test_loss",
+            "final_train_loss",
+            "images_per_sec",
+            "elapsed_secs",
+        ])
+    );
+    let opt = |v: Option<f32>| v.map_or_else(String::new, |v| format!("{v:.4}"));
+    for s in summaries {
+        println!(
+            "{}",
+            csv::row(&[
+                s.path.clone(),
+                s.dataset.clone(),
+                s.model.clone(),
+                s.optimizer.clone(),
+                s.lr_scheduler.clone(),
+                s.batch_size.to_string(),
+                s.epochs.to_string(),
+                s.best_epoch.to_string(),
+                format!("{:.4}", s.val_acc),
+                format!("{:.4}", s.val_loss),
+                opt(s.test_acc),
+                opt(s.test_loss),
+                format!("{:.4}", s.final_train_loss),
+                format!("{:.1}", s.images_per_sec),
+                format!("{:.1}", s.elapsed_secs),
+            ])
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(epoch: u32, step: usize, loss: f32) -> MetricsRecord {
+        MetricsRecord::Step {
+            epoch,
+            step,
+            loss,
+            lr: 0.1,
+            images_per_sec: 100.0,
+            elapsed_secs: step as f64,
+        }
+    }
+
+    fn epoch(epoch: u32, step: usize, val_acc: f32) -> MetricsRecord {
+        MetricsRecord::Epoch {
+            epoch,
+            step,
+            train_loss: 1.0 / epoch as f32,
+            val_loss: 1.0 - val_acc,
+            val_acc,
+            lr: 0.1,
+            images_per_sec: 100.0,
+            elapsed_secs: step as f64,
+        }
+    }
+
+    fn run(config: &DatasetConfig, resumed_from: Option<usize>) -> MetricsRecord {
+        MetricsRecord::Run {
+            dataset: config.name.clone(),
+            model: config.model.model_type.clone(),
+            optimizer: config.training.optimizer.clone(),
+            lr_scheduler: config.training.lr_scheduler.clone(),
+            epochs: 3,
+            batch_size: 64,
+            learning_rate: 0.1,
+            resumed_from,
+        }
+    }
+
+    fn test(best_epoch: u32, test_acc: f32) -> MetricsRecord {
+        MetricsRecord::Test {
+            best_epoch,
+            test_loss: 1.0 - test_acc,
+            test_acc,
+            samples: 10,
+        }
+    }
+
+    #[test]
+    fn resume_keeps_records_up_to_the_resume_step_and_summarizes() {
+        let dir = std::env::temp_dir().join(format!("metrics-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let mut config: DatasetConfig = serde_json::from_str(include_str!("../configs/mnist.json")).expect("config");
+        config.artifacts.dir = dir.to_string_lossy().into_owned();
+
+        // 最初の実行: 3 エポック (2 ステップずつ) とテストまで
+        let mut logger = MetricsLogger::create(&config, None, 0.0).unwrap();
+        logger.log(&run(&config, None)).unwrap();
+        for e in 1..=3 {
+            logger.log(&step(e, 2 * e as usize - 1, 1.0)).unwrap();
+            logger.log(&step(e, 2 * e as usize, 1.0)).unwrap();
+            logger.log(&epoch(e, 2 * e as usize, 0.5)).unwrap();
+        }
+        logger.log(&test(1, 0.5)).unwrap();
+        drop(logger);
+
+        // エポック 2 の途中 (step 3) から再開すると、step 3 より後とテスト結果は捨てる
+        let mut logger = MetricsLogger::create(&config, Some(3), 3.0).unwrap();
+        let kept = read_records(Path::new(&config.get_metrics_path())).unwrap();
+        let steps: Vec<Option<usize>> = kept.iter().map(MetricsRecord::step).collect();
+        assert_eq!(steps, [None, Some(1), Some(2), Some(2), Some(3)]);
+
+        logger.log(&run(&config, Some(3))).unwrap();
+        logger.log(&step(2, 4, 0.5)).unwrap();
+        logger.log(&epoch(2, 4, 0.9)).unwrap();
+        logger.log(&step(3, 5, 0.5)).unwrap();
+        logger.log(&step(3, 6, 0.5)).unwrap();
+        logger.log(&epoch(3, 6, 0.8)).unwrap();
+        logger.log(&test(2, 0.85)).unwrap();
+        drop(logger);
+
+        let summary = summarize(&config.artifacts.dir).unwrap();
+        assert_eq!(summary.dataset, "mnist");
+        assert_eq!(summary.epochs, 3);
+        assert_eq!(summary.best_epoch, 2);
+        assert_eq!(summary.val_acc, 0.9);
+        assert_eq!(summary.test_acc, Some(0.85));
+        assert_eq!(summary.final_train_loss, 1.0 / 3.0);
+        assert_eq!(summary.elapsed_secs, 6.0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
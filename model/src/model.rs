@@ -1,11 +1,14 @@
 // src/model.rs
-use crate::config::DatasetConfig;
+use crate::config::{ConfigError, DatasetConfig};
+use crate::summary::Tracer;
+use anyhow::Result;
 use burn::nn::{
-    Dropout, DropoutConfig, Linear, LinearConfig, PaddingConfig2d, Relu,
+    BatchNorm, BatchNormConfig, Dropout, DropoutConfig, Linear, LinearConfig, PaddingConfig2d, Relu,
     conv::{Conv2d, Conv2dConfig},
-    pool::{MaxPool2d, MaxPool2dConfig},
+    pool::{AdaptiveAvgPool2d, AdaptiveAvgPool2dConfig, MaxPool2d, MaxPool2dConfig},
 };
 use burn::prelude::*;
+use burn::tensor::{Distribution, activation::softmax};
 
 #[derive(Module, Debug)]
 pub struct LeNet<B: Backend> {
@@ -30,14 +33,110 @@ pub struct CifarNet<B: Backend> {
     act: Relu,
 }
 
+/// CIFAR 用の ResNet (He et al. 2016, 深さ 6n+2: ResNet-8/14/20/...)
+///
+/// 3x3 conv → 幅 w, 2w, 4w の3ステージ (各 n ブロック) → Global Average Pooling → 全結合
+#[derive(Module, Debug)]
+pub struct ResNet<B: Backend> {
+    conv: Conv2d<B>,
+    bn: BatchNorm<B, 2>,
+    blocks: Vec<BasicBlock<B>>,
+    pool: AdaptiveAvgPool2d,
+    fc: Linear<B>,
+    act: Relu,
+}
+
+/// 3x3 conv を2つ重ねた残差ブロック (形が変わるときは 1x1 conv で射影したスキップ接続)
+#[derive(Module, Debug)]
+pub struct BasicBlock<B: Backend> {
+    conv1: Conv2d<B>,
+    bn1: BatchNorm<B, 2>,
+    conv2: Conv2d<B>,
+    bn2: BatchNorm<B, 2>,
+    shortcut: Option<Projection<B>>,
+    act: Relu,
+}
+
+#[derive(Module, Debug)]
+pub struct Projection<B: Backend> {
+    conv: Conv2d<B>,
+    bn: BatchNorm<B, 2>,
+}
+
+/// forward での Dropout / BatchNorm の動作
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForwardMode {
+    /// 学習: Dropout を適用し、BatchNorm はバッチの統計量を使って移動平均を更新する
+    Train,
+    /// 推論: Dropout なし、BatchNorm は移動平均を使う
+    Eval,
+    /// MC Dropout 推論: Dropout だけ学習時と同じように適用する
+    McDropout,
+}
+
 pub trait ModelTrait<B: Backend> {
-    fn forward(&self, x: Tensor<B, 4>) -> Tensor<B, 2>;
+    fn forward_mode(&self, x: Tensor<B, 4>, mode: ForwardMode) -> Tensor<B, 2>;
+
+    /// 推論 (`ForwardMode::Eval`)
+    fn forward(&self, x: Tensor<B, 4>) -> Tensor<B, 2> {
+        self.forward_mode(x, ForwardMode::Eval)
+    }
+
+    /// 有効な Dropout (確率 > 0) を含むか (なければ MC Dropout は毎回同じ推論になる)
+    fn has_dropout(&self) -> bool {
+        false
+    }
+}
+
+/// `samples` 回の MC Dropout 推論の平均確率 [B, num_classes]
+pub fn mc_dropout_probs<B: Backend>(model: &dyn ModelTrait<B>, x: Tensor<B, 4>, samples: usize) -> Tensor<B, 2> {
+    let samples = samples.max(1);
+    let sample = || softmax(model.forward_mode(x.clone(), ForwardMode::McDropout), 1);
+    (1..samples)
+        .fold(sample(), |sum, _| sum + sample())
+        .div_scalar(samples as f32)
+}
+
+/// 予測分布のエントロピー (nats, 0 ～ ln(クラス数))
+pub fn predictive_entropy(probs: &[f32]) -> f32 {
+    -probs.iter().filter(|&&p| p > 0.0).map(|&p| p * p.ln()).sum::<f32>()
+}
+
+/// `mode` に従う Dropout (`Dropout::forward` と違いバックエンドの autodiff の有無によらない)
+pub fn dropout<B: Backend, const D: usize>(dropout: &Dropout, x: Tensor<B, D>, mode: ForwardMode) -> Tensor<B, D> {
+    if mode == ForwardMode::Eval || dropout.prob == 0.0 {
+        return x;
+    }
+    let keep = 1.0 - dropout.prob;
+    let mask = x.random_like(Distribution::Bernoulli(keep));
+    (x * mask).div_scalar(keep)
+}
+
+/// `mode` に従う BatchNorm (学習時以外は移動平均で正規化する)
+pub fn batch_norm<B: Backend, const D: usize, const DI: usize>(
+    bn: &BatchNorm<B, D>,
+    x: Tensor<B, DI>,
+    mode: ForwardMode,
+) -> Tensor<B, DI> {
+    if mode == ForwardMode::Train {
+        return bn.forward(x);
+    }
+    let device = x.device();
+    let mut shape = [1; DI];
+    shape[1] = x.dims()[1];
+    let mean = bn.running_mean.value().to_device(&device).reshape(shape);
+    let var = bn.running_var.value().to_device(&device).reshape(shape);
+    let gamma = bn.gamma.val().reshape(shape);
+    let beta = bn.beta.val().reshape(shape);
+    (x - mean) / var.add_scalar(bn.epsilon).sqrt() * gamma + beta
 }
 
 impl<B: Backend> LeNet<B> {
-    pub fn new(device: &B::Device, config: &DatasetConfig) -> Self {
+    pub fn new(device: &B::Device, config: &DatasetConfig) -> Result<Self> {
+        check_lenet(config)?;
         let conv1_out = config.model.conv1_out.unwrap_or(32);
         let conv2_out = config.model.conv2_out.unwrap_or(64);
+        let fc1_out = config.model.fc1_out.unwrap_or(128);
 
         let conv1 = Conv2dConfig::new([config.input_channels, conv1_out], [5, 5])
             .with_padding(PaddingConfig2d::Explicit(2, 2))
@@ -48,25 +147,38 @@ impl<B: Backend> LeNet<B> {
         let pool = MaxPool2dConfig::new([2, 2]).init();
 
         // 入力サイズから最終的な特徴マップサイズを計算
-        let final_size = config.input_size[0] / 4; // 2回のプーリングで1/4
-        let fc1 = LinearConfig::new(conv2_out * final_size * final_size, config.model.fc1_out)
+        let [height, width] = config.input_size.map(|s| s / 4); // 2回のプーリングで1/4
+        let fc1 = LinearConfig::new(conv2_out * height * width, fc1_out)
             .init(device);
-        let fc2 = LinearConfig::new(config.model.fc1_out, config.num_classes).init(device);
+        let fc2 = LinearConfig::new(fc1_out, config.num_classes).init(device);
         let act = Relu::new();
 
-        Self {
+        Ok(Self {
             conv1,
             conv2,
             pool,
             fc1,
             fc2,
             act,
-        }
+        })
+    }
+
+    pub fn summary(&self, tracer: &mut Tracer) -> Result<()> {
+        tracer.conv("conv1", &self.conv1)?;
+        tracer.activation("relu1");
+        tracer.max_pool("pool1", &self.pool)?;
+        tracer.conv("conv2", &self.conv2)?;
+        tracer.activation("relu2");
+        tracer.max_pool("pool2", &self.pool)?;
+        tracer.flatten("flatten")?;
+        tracer.linear("fc1", &self.fc1)?;
+        tracer.activation("relu3");
+        tracer.linear("fc2", &self.fc2)
     }
 }
 
 impl<B: Backend> ModelTrait<B> for LeNet<B> {
-    fn forward(&self, x: Tensor<B, 4>) -> Tensor<B, 2> {
+    fn forward_mode(&self, x: Tensor<B, 4>, _mode: ForwardMode) -> Tensor<B, 2> {
         let x = self.pool.forward(self.act.forward(self.conv1.forward(x)));
         let x = self.pool.forward(self.act.forward(self.conv2.forward(x)));
 
@@ -81,10 +193,12 @@ impl<B: Backend> ModelTrait<B> for LeNet<B> {
 }
 
 impl<B: Backend> CifarNet<B> {
-    pub fn new(device: &B::Device, config: &DatasetConfig) -> Self {
+    pub fn new(device: &B::Device, config: &DatasetConfig) -> Result<Self> {
+        check_cifar_net(config)?;
         let conv1_out = config.model.conv1_out.unwrap_or(64);
         let conv2_out = config.model.conv2_out.unwrap_or(128);
         let conv3_out = config.model.conv3_out.unwrap_or(256);
+        let fc1_out = config.model.fc1_out.unwrap_or(512);
         let fc2_out = config.model.fc2_out.unwrap_or(256);
 
         let conv1 = Conv2dConfig::new([config.input_channels, conv1_out], [3, 3])
@@ -98,14 +212,15 @@ impl<B: Backend> CifarNet<B> {
             .init(device);
         let pool = MaxPool2dConfig::new([2, 2]).init();
 
-        // CIFAR-10: 32x32 -> 16x16 -> 8x8 -> 4x4
-        let fc1 = LinearConfig::new(conv3_out * 4 * 4, config.model.fc1_out).init(device);
-        let fc2 = LinearConfig::new(config.model.fc1_out, fc2_out).init(device);
+        // 3回のプーリングで1/8 (CIFAR-10: 32x32 -> 16x16 -> 8x8 -> 4x4)
+        let [height, width] = config.input_size.map(|s| s / 8);
+        let fc1 = LinearConfig::new(conv3_out * height * width, fc1_out).init(device);
+        let fc2 = LinearConfig::new(fc1_out, fc2_out).init(device);
         let fc3 = LinearConfig::new(fc2_out, config.num_classes).init(device);
         let dropout = DropoutConfig::new(0.5).init();
         let act = Relu::new();
 
-        Self {
+        Ok(Self {
             conv1,
             conv2,
             conv3,
@@ -115,12 +230,28 @@ impl<B: Backend> CifarNet<B> {
             fc3,
             dropout,
             act,
+        })
+    }
+
+    pub fn summary(&self, tracer: &mut Tracer) -> Result<()> {
+        for (i, conv) in [&self.conv1, &self.conv2, &self.conv3].into_iter().enumerate() {
+            tracer.conv(&format!("conv{}", i + 1), conv)?;
+            tracer.activation(&format!("relu{}", i + 1));
+            tracer.max_pool(&format!("pool{}", i + 1), &self.pool)?;
         }
+        tracer.flatten("flatten")?;
+        tracer.linear("fc1", &self.fc1)?;
+        tracer.activation("relu4");
+        tracer.dropout("dropout1");
+        tracer.linear("fc2", &self.fc2)?;
+        tracer.activation("relu5");
+        tracer.dropout("dropout2");
+        tracer.linear("fc3", &self.fc3)
     }
 }
 
 impl<B: Backend> ModelTrait<B> for CifarNet<B> {
-    fn forward(&self, x: Tensor<B, 4>) -> Tensor<B, 2> {
+    fn forward_mode(&self, x: Tensor<B, 4>, mode: ForwardMode) -> Tensor<B, 2> {
         // x: [B,3,32,32]
         let x = self.pool.forward(self.act.forward(self.conv1.forward(x))); // -> [B,64,16,16]
         let x = self.pool.forward(self.act.forward(self.conv2.forward(x))); // -> [B,128,8,8]
@@ -130,8 +261,258 @@ impl<B: Backend> ModelTrait<B> for CifarNet<B> {
         let b = dims[0];
         let x = x.reshape([b, dims[1] * dims[2] * dims[3]]);
 
-        let x = self.dropout.forward(self.act.forward(self.fc1.forward(x)));
-        let x = self.dropout.forward(self.act.forward(self.fc2.forward(x)));
+        let x = dropout(&self.dropout, self.act.forward(self.fc1.forward(x)), mode);
+        let x = dropout(&self.dropout, self.act.forward(self.fc2.forward(x)), mode);
         self.fc3.forward(x)
     }
+
+    fn has_dropout(&self) -> bool {
+        self.dropout.prob > 0.0
+    }
+}
+
+impl<B: Backend> ResNet<B> {
+    pub fn new(device: &B::Device, config: &DatasetConfig) -> Result<Self> {
+        let (depth, width) = check_resnet(config)?;
+        let blocks_per_stage = (depth - 2) / 6;
+
+        let conv = conv3x3(config.input_channels, width, 1, device);
+        let bn = BatchNormConfig::new(width).init(device);
+
+        // ステージ2・3の最初のブロックで解像度を半分・チャンネルを2倍にする
+        let mut blocks = Vec::new();
+        let mut channels = width;
+        for stage in 0..3 {
+            let out = width << stage;
+            for i in 0..blocks_per_stage {
+                let stride = if stage > 0 && i == 0 { 2 } else { 1 };
+                blocks.push(BasicBlock::new(channels, out, stride, device));
+                channels = out;
+            }
+        }
+
+        Ok(Self {
+            conv,
+            bn,
+            blocks,
+            pool: AdaptiveAvgPool2dConfig::new([1, 1]).init(),
+            fc: LinearConfig::new(channels, config.num_classes).init(device),
+            act: Relu::new(),
+        })
+    }
+
+    pub fn summary(&self, tracer: &mut Tracer) -> Result<()> {
+        tracer.conv("conv", &self.conv)?;
+        tracer.batch_norm("bn", &self.bn)?;
+        tracer.activation("relu");
+        for (i, block) in self.blocks.iter().enumerate() {
+            block.summary(tracer, &format!("block{}", i + 1))?;
+        }
+        tracer.adaptive_avg_pool("pool", &self.pool)?;
+        tracer.flatten("flatten")?;
+        tracer.linear("fc", &self.fc)
+    }
+}
+
+impl<B: Backend> ModelTrait<B> for ResNet<B> {
+    fn forward_mode(&self, x: Tensor<B, 4>, mode: ForwardMode) -> Tensor<B, 2> {
+        // x: [B,C,32,32] -> [B,w,32,32] -> [B,2w,16,16] -> [B,4w,8,8]
+        let mut x = self.act.forward(batch_norm(&self.bn, self.conv.forward(x), mode));
+        for block in &self.blocks {
+            x = block.forward(x, mode);
+        }
+        let x = self.pool.forward(x); // -> [B,4w,1,1]
+        let [b, c, _, _] = x.dims();
+        self.fc.forward(x.reshape([b, c]))
+    }
+}
+
+impl<B: Backend> BasicBlock<B> {
+    fn new(in_channels: usize, out_channels: usize, stride: usize, device: &B::Device) -> Self {
+        let shortcut = (stride != 1 || in_channels != out_channels).then(|| Projection {
+            conv: Conv2dConfig::new([in_channels, out_channels], [1, 1])
+                .with_stride([stride, stride])
+                .with_bias(false)
+                .init(device),
+            bn: BatchNormConfig::new(out_channels).init(device),
+        });
+
+        Self {
+            conv1: conv3x3(in_channels, out_channels, stride, device),
+            bn1: BatchNormConfig::new(out_channels).init(device),
+            conv2: conv3x3(out_channels, out_channels, 1, device),
+            bn2: BatchNormConfig::new(out_channels).init(device),
+            shortcut,
+            act: Relu::new(),
+        }
+    }
+
+    fn forward(&self, x: Tensor<B, 4>, mode: ForwardMode) -> Tensor<B, 4> {
+        let identity = match &self.shortcut {
+            Some(projection) => batch_norm(&projection.bn, projection.conv.forward(x.clone()), mode),
+            None => x.clone(),
+        };
+        let out = self.act.forward(batch_norm(&self.bn1, self.conv1.forward(x), mode));
+        let out = batch_norm(&self.bn2, self.conv2.forward(out), mode);
+        self.act.forward(out + identity)
+    }
+
+    fn summary(&self, tracer: &mut Tracer, name: &str) -> Result<()> {
+        let input = tracer.shape();
+        tracer.conv(&format!("{name}.conv1"), &self.conv1)?;
+        tracer.batch_norm(&format!("{name}.bn1"), &self.bn1)?;
+        tracer.activation(&format!("{name}.relu1"));
+        tracer.conv(&format!("{name}.conv2"), &self.conv2)?;
+        tracer.batch_norm(&format!("{name}.bn2"), &self.bn2)?;
+        let out = tracer.shape();
+        // ショートカットはブロックの入力から (恒等写像なら形はそのまま)
+        tracer.set_shape(input);
+        if let Some(projection) = &self.shortcut {
+            tracer.conv(&format!("{name}.shortcut.conv"), &projection.conv)?;
+            tracer.batch_norm(&format!("{name}.shortcut.bn"), &projection.bn)?;
+        }
+        tracer.add(&format!("{name}.add"), out)?;
+        tracer.activation(&format!("{name}.relu2"));
+        Ok(())
+    }
+}
+
+/// lenet の設定が入力の大きさと合うか確認する
+pub fn check_lenet(config: &DatasetConfig) -> Result<(), ConfigError> {
+    let model = &config.model;
+    check_widths("lenet", &[("conv1_out", model.conv1_out), ("conv2_out", model.conv2_out), ("fc1_out", model.fc1_out)])?;
+    check_input_size(config, "lenet", 4)
+}
+
+/// cifar_net の設定が入力の大きさと合うか確認する
+pub fn check_cifar_net(config: &DatasetConfig) -> Result<(), ConfigError> {
+    let model = &config.model;
+    check_widths(
+        "cifar_net",
+        &[
+            ("conv1_out", model.conv1_out),
+            ("conv2_out", model.conv2_out),
+            ("conv3_out", model.conv3_out),
+            ("fc1_out", model.fc1_out),
+            ("fc2_out", model.fc2_out),
+        ],
+    )?;
+    check_input_size(config, "cifar_net", 8)
+}
+
+/// resnet の層数とチャンネル数 (depth, base_width) を確認して返す
+pub fn check_resnet(config: &DatasetConfig) -> Result<(usize, usize), ConfigError> {
+    let depth = config.model.depth.unwrap_or(20);
+    let width = config.model.base_width.unwrap_or(16);
+    if depth < 8 || !(depth - 2).is_multiple_of(6) {
+        return Err(ConfigError::Model {
+            model_type: "resnet",
+            reason: format!("model.depth は 6n+2 (8, 14, 20, 32, ...) にしてください: {}", depth),
+        });
+    }
+    check_widths("resnet", &[("base_width", Some(width))])?;
+    Ok((depth, width))
+}
+
+fn check_widths(model_type: &'static str, widths: &[(&str, Option<usize>)]) -> Result<(), ConfigError> {
+    match widths.iter().find(|(_, width)| *width == Some(0)) {
+        Some((field, _)) => Err(ConfigError::Model {
+            model_type,
+            reason: format!("model.{} は 1 以上にしてください", field),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// プーリングで割り切れない `input_size` は全結合層の入力数と合わなくなるので弾く
+fn check_input_size(config: &DatasetConfig, model_type: &'static str, divisor: usize) -> Result<(), ConfigError> {
+    if config.input_size.iter().any(|s| *s == 0 || !s.is_multiple_of(divisor)) {
+        return Err(ConfigError::Model {
+            model_type,
+            reason: format!("input_size は {} の倍数にしてください: {:?}", divisor, config.input_size),
+        });
+    }
+    Ok(())
+}
+
+/// BatchNorm の前に置く 3x3 conv (バイアスは BatchNorm の beta と重複するので持たない)
+fn conv3x3<B: Backend>(in_channels: usize, out_channels: usize, stride: usize, device: &B::Device) -> Conv2d<B> {
+    Conv2dConfig::new([in_channels, out_channels], [3, 3])
+        .with_stride([stride, stride])
+        .with_padding(PaddingConfig2d::Explicit(1, 1))
+        .with_bias(false)
+        .init(device)
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use burn::backend::NdArray;
+    use burn::record::{BinBytesRecorder, FullPrecisionSettings, Recorder};
+
+    type B = NdArray;
+
+    fn resnet_config(depth: usize) -> DatasetConfig {
+        let mut config: DatasetConfig =
+            serde_json::from_str(include_str!("../configs/cifar10_resnet20.json")).expect("config");
+        config.model.depth = Some(depth);
+        config.model.base_width = Some(4);
+        config
+    }
+
+    #[test]
+    fn resnet_depth_and_bin_round_trip() {
+        let _rng = crate::augment::lock_backend_rng();
+        let device = Default::default();
+        assert!(ResNet::<B>::new(&device, &resnet_config(10)).is_err());
+
+        let model = ResNet::<B>::new(&device, &resnet_config(14)).unwrap();
+        assert_eq!(model.blocks.len(), 6);
+        // Dropout がないので MC Dropout は使えない
+        assert!(!model.has_dropout());
+        let x = Tensor::<B, 4>::random([2, 3, 32, 32], burn::tensor::Distribution::Default, &device);
+        let y = model.forward(x.clone());
+        assert_eq!(y.dims(), [2, 10]);
+
+        // model.bin (BinBytesRecorder) から BatchNorm の統計量も含めて復元できる
+        let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+        let bytes = recorder.record(model.into_record(), ()).unwrap();
+        let record = recorder.load(bytes, &device).unwrap();
+        let loaded = ResNet::<B>::new(&device, &resnet_config(14)).unwrap().load_record(record);
+        y.into_data().assert_eq(&loaded.forward(x).into_data(), true);
+    }
+
+    #[test]
+    fn forward_mode_controls_dropout_and_batch_norm() {
+        let _rng = crate::augment::lock_backend_rng();
+        use burn::backend::Autodiff;
+        use burn::module::AutodiffModule;
+
+        let device = Default::default();
+        let mut config: DatasetConfig = serde_json::from_str(include_str!("../configs/cifar10.json")).expect("config");
+        config.model.conv1_out = Some(4);
+        config.model.conv2_out = Some(4);
+        config.model.conv3_out = Some(4);
+        let model = CifarNet::<B>::new(&device, &config).expect("cifar_net");
+        let x = Tensor::<B, 4>::random([2, 3, 32, 32], burn::tensor::Distribution::Default, &device);
+
+        // 推論は毎回同じ、MC Dropout は毎回変わる
+        let eval = model.forward(x.clone()).into_data();
+        eval.assert_eq(&model.forward(x.clone()).into_data(), true);
+        let a = model.forward_mode(x.clone(), ForwardMode::McDropout).into_data();
+        let b = model.forward_mode(x.clone(), ForwardMode::McDropout).into_data();
+        assert_ne!(a.to_vec::<f32>().unwrap(), b.to_vec::<f32>().unwrap());
+        assert!(model.has_dropout());
+
+        let probs = mc_dropout_probs(&model, x.clone(), 4).sum_dim(1).into_data();
+        probs.assert_approx_eq::<f32>(&TensorData::from([[1.0f32], [1.0]]), Default::default());
+        assert!((predictive_entropy(&[0.25; 4]) - 4f32.ln()).abs() < 1e-6);
+        assert_eq!(predictive_entropy(&[1.0, 0.0]), 0.0);
+
+        // autodiff バックエンドでも Eval なら BatchNorm は移動平均を使う
+        let resnet = ResNet::<Autodiff<B>>::new(&device, &resnet_config(8)).unwrap();
+        let eval = resnet.valid().forward(x.clone()).into_data();
+        let ad = resnet.forward(Tensor::from_inner(x)).inner().into_data();
+        eval.assert_approx_eq::<f32>(&ad, Default::default());
+    }
 }
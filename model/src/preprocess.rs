@@ -0,0 +1,537 @@
+// src/preprocess.rs
+// 画素値 -> 正規化済みテンソルの変換。学習 (バッチャー)・CLI推論・wasm の全経路がここを通る。
+use crate::config::{AlphaMode, DatasetConfig, PreprocessingConfig, ResizeFilter, ResizeMode};
+use anyhow::{Result, anyhow};
+use burn::prelude::*;
+use image::{DynamicImage, GrayImage, ImageBuffer, Luma, Pixel, Rgb, RgbImage, imageops, imageops::FilterType};
+
+/// 入力画素の並び
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+pub enum PixelLayout {
+    /// [H, W, C] (画像ファイル・Canvas の RGB 並び)
+    Hwc,
+    /// [C, H, W] (CIFAR-10 バイナリ・テンソルと同じ並び, wasm の入力)
+    Chw,
+}
+
+/// `DatasetConfig` の入力形状と正規化に従って画素をテンソルに変換する
+#[derive(Clone, Debug)]
+pub struct Preprocessor {
+    pub channels: usize,
+    pub height: usize,
+    pub width: usize,
+    mean: Vec<f32>,
+    std: Vec<f32>,
+    preprocessing: PreprocessingConfig,
+}
+
+impl Preprocessor {
+    pub fn new(config: &DatasetConfig) -> Result<Self> {
+        let channels = config.input_channels;
+        let [height, width] = config.input_size;
+        let normalization = &config.training.normalization;
+        if config.preprocessing.resize == ResizeMode::MnistCanonicalize && channels != 1 {
+            return Err(anyhow!(
+                "mnist_canonicalize はグレースケール (input_channels = 1) のみ対応です"
+            ));
+        }
+
+        Ok(Self {
+            channels,
+            height,
+            width,
+            mean: normalization.mean.per_channel(channels)?,
+            std: normalization.std.per_channel(channels)?,
+            preprocessing: config.preprocessing.clone(),
+        })
+    }
+
+    /// 1枚あたりの画素数 (C*H*W)
+    pub fn image_len(&self) -> usize {
+        self.channels * self.height * self.width
+    }
+
+    /// 0-255 の u8 画素 1枚を [1,C,H,W] の正規化済みテンソルに変換
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn pixels_u8<B: Backend>(
+        &self,
+        pixels: &[u8],
+        layout: PixelLayout,
+        device: &B::Device,
+    ) -> Result<Tensor<B, 4>> {
+        let pixels: Vec<f32> = pixels.iter().map(|&p| p as f32).collect();
+        self.pixels_f32(&pixels, layout, device)
+    }
+
+    /// 0-255 の f32 画素 1枚を [1,C,H,W] の正規化済みテンソルに変換 (wasm の入力形式)
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    pub fn pixels_f32<B: Backend>(
+        &self,
+        pixels: &[f32],
+        layout: PixelLayout,
+        device: &B::Device,
+    ) -> Result<Tensor<B, 4>> {
+        if pixels.len() != self.image_len() {
+            return Err(anyhow!(
+                "入力の画素数が一致しません: {} (期待値 {} = {}x{}x{})",
+                pixels.len(),
+                self.image_len(),
+                self.channels,
+                self.height,
+                self.width
+            ));
+        }
+
+        let chw = match layout {
+            PixelLayout::Chw => pixels.to_vec(),
+            PixelLayout::Hwc => self.hwc_to_chw(pixels),
+        };
+        // CLI推論と同じく、描画・撮影した数字を MNIST の形に揃える
+        if self.preprocessing.resize == ResizeMode::MnistCanonicalize {
+            let gray = GrayImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+                Luma([chw[y as usize * self.width + x as usize].round().clamp(0.0, 255.0) as u8])
+            });
+            let canonical = canonicalize_digit(&gray, self.width as u32, self.height as u32, self.filter());
+            return Ok(self.batch(canonical.iter().map(|&p| p as f32).collect(), 1, device));
+        }
+        Ok(self.batch(chw, 1, device))
+    }
+
+    /// 画像を `preprocessing` の設定で入力サイズに合わせ、CHW 順の 0-255 の画素にする
+    /// (CLI推論・評価, `batch` でまとめてテンソル化)
+    pub fn image_pixels(&self, img: &DynamicImage) -> Result<Vec<f32>> {
+        let img = match self.preprocessing.alpha {
+            AlphaMode::Composite if img.color().has_alpha() => {
+                DynamicImage::ImageRgb8(composite(img, self.preprocessing.alpha_background))
+            }
+            _ => img.clone(),
+        };
+
+        let pad = Rgb(self.preprocessing.pad_color);
+        let pixels = match self.channels {
+            1 if self.preprocessing.resize == ResizeMode::MnistCanonicalize => {
+                canonicalize_digit(&img.to_luma8(), self.width as u32, self.height as u32, self.filter())
+                    .into_raw()
+            }
+            1 => self.fit(&img.to_luma8(), pad.to_luma()).into_raw(),
+            3 => self.fit(&img.to_rgb8(), pad).into_raw(),
+            _ => return Err(anyhow!("未対応のチャンネル数: {}", self.channels)),
+        };
+        let pixels: Vec<f32> = pixels.iter().map(|&p| p as f32).collect();
+        Ok(self.hwc_to_chw(&pixels))
+    }
+
+    /// `resize` の方法で画像を入力サイズにする
+    fn fit<P>(&self, img: &ImageBuffer<P, Vec<u8>>, pad: P) -> ImageBuffer<P, Vec<u8>>
+    where
+        P: Pixel<Subpixel = u8> + 'static,
+    {
+        let (width, height) = (self.width as u32, self.height as u32);
+        let (src_w, src_h) = img.dimensions();
+        if (src_w, src_h) == (width, height) {
+            return img.clone();
+        }
+        let filter = self.filter();
+
+        // 縦横比を保つ場合の拡大率 (letterbox は収まる方, shorter_side_crop は覆う方)
+        let scale_x = width as f64 / src_w as f64;
+        let scale_y = height as f64 / src_h as f64;
+        let scale = match self.preprocessing.resize {
+            ResizeMode::Stretch => return imageops::resize(img, width, height, filter),
+            ResizeMode::Letterbox => scale_x.min(scale_y),
+            ResizeMode::ShorterSideCrop => scale_x.max(scale_y),
+            ResizeMode::CenterCrop => 1.0,
+            ResizeMode::MnistCanonicalize => unreachable!("image_pixels で処理済み"),
+        };
+        let scaled = if scale == 1.0 {
+            img.clone()
+        } else {
+            let w = ((src_w as f64 * scale).round() as u32).max(1);
+            let h = ((src_h as f64 * scale).round() as u32).max(1);
+            imageops::resize(img, w, h, filter)
+        };
+
+        // 中央に配置 (はみ出した部分は切り落とし、足りない部分は pad)
+        let mut canvas = ImageBuffer::from_pixel(width, height, pad);
+        let x = (width as i64 - scaled.width() as i64) / 2;
+        let y = (height as i64 - scaled.height() as i64) / 2;
+        imageops::replace(&mut canvas, &scaled, x, y);
+        canvas
+    }
+
+    fn filter(&self) -> FilterType {
+        match self.preprocessing.filter {
+            ResizeFilter::Nearest => FilterType::Nearest,
+            ResizeFilter::Bilinear => FilterType::Triangle,
+            ResizeFilter::Lanczos => FilterType::Lanczos3,
+        }
+    }
+
+    /// CHW 順に並べた 0-255 の画素 `count` 枚分を [N,C,H,W] の正規化済みテンソルに変換 (バッチャー)
+    pub fn batch<B: Backend>(&self, chw: Vec<f32>, count: usize, device: &B::Device) -> Tensor<B, 4> {
+        self.normalize(self.raw_batch(chw, count, device))
+    }
+
+    /// CHW 順に並べた 0-255 の画素 `count` 枚分を正規化せずに [N,C,H,W] にする
+    pub fn raw_batch<B: Backend>(&self, chw: Vec<f32>, count: usize, device: &B::Device) -> Tensor<B, 4> {
+        Tensor::<B, 1>::from_floats(chw.as_slice(), device).reshape([
+            count,
+            self.channels,
+            self.height,
+            self.width,
+        ])
+    }
+
+    /// 0-255 の画素値 [N,C,H,W] を [0,1] にしてからチャンネルごとに (x - mean) / std
+    pub fn normalize<B: Backend>(&self, images: Tensor<B, 4>) -> Tensor<B, 4> {
+        let device = images.device();
+        let mean = Tensor::<B, 1>::from_floats(self.mean.as_slice(), &device)
+            .reshape([1, self.channels, 1, 1]);
+        let std = Tensor::<B, 1>::from_floats(self.std.as_slice(), &device)
+            .reshape([1, self.channels, 1, 1]);
+
+        ((images / 255.0) - mean) / std
+    }
+
+    /// データセットの画像形状が設定と一致するか確認
+    pub fn expect_shape(&self, dataset: &str, channels: usize, height: usize, width: usize) -> Result<()> {
+        if [self.channels, self.height, self.width] != [channels, height, width] {
+            return Err(anyhow!(
+                "{}の画像は [{}, {}, {}] ですが、設定は [{}, {}, {}] です",
+                dataset,
+                channels,
+                height,
+                width,
+                self.channels,
+                self.height,
+                self.width
+            ));
+        }
+        Ok(())
+    }
+
+    fn hwc_to_chw(&self, pixels: &[f32]) -> Vec<f32> {
+        let c = self.channels;
+        (0..c)
+            .flat_map(|ch| pixels.iter().skip(ch).step_by(c).copied())
+            .collect()
+    }
+}
+
+/// 手書き数字を MNIST の作り方に合わせて `width` x `height` (28x28 なら数字は長辺 20px) に配置
+///
+/// 1. 縁の平均が明るければ (白地に黒インク) 反転する
+/// 2. 大津の閾値以下を背景 (0) とし、残りを 0-255 に引き伸ばす
+/// 3. 数字の外接矩形で切り出し、縦横比を保って長辺を 20/28 に縮小する
+/// 4. 輝度の重心が中央に来るように配置する
+fn canonicalize_digit(img: &GrayImage, width: u32, height: u32, filter: FilterType) -> GrayImage {
+    let mut canvas = GrayImage::new(width, height);
+    let (w, h) = img.dimensions();
+    if w == 0 || h == 0 {
+        return canvas;
+    }
+
+    let border: Vec<u32> = img
+        .enumerate_pixels()
+        .filter(|(x, y, _)| *x == 0 || *y == 0 || *x == w - 1 || *y == h - 1)
+        .map(|(_, _, p)| p[0] as u32)
+        .collect();
+    let invert = border.iter().sum::<u32>() > 127 * border.len() as u32;
+    let mut ink = img.clone();
+    if invert {
+        imageops::invert(&mut ink);
+    }
+
+    // 一様な画像には数字がない
+    let min = ink.pixels().map(|p| p[0]).min().unwrap_or(0);
+    let max = ink.pixels().map(|p| p[0]).max().unwrap_or(0);
+    let threshold = otsu_threshold(&ink);
+    if min == max || max <= threshold {
+        return canvas;
+    }
+    let span = (max - threshold) as f32;
+    for p in ink.pixels_mut() {
+        p[0] = if p[0] > threshold {
+            ((p[0] - threshold) as f32 / span * 255.0).round() as u8
+        } else {
+            0
+        };
+    }
+
+    // 外接矩形 (閾値を超えた画素は必ずあるので空にはならない)
+    let (mut x0, mut y0, mut x1, mut y1) = (w, h, 0, 0);
+    for (x, y, p) in ink.enumerate_pixels() {
+        if p[0] > 0 {
+            (x0, y0, x1, y1) = (x0.min(x), y0.min(y), x1.max(x), y1.max(y));
+        }
+    }
+    let digit = imageops::crop_imm(&ink, x0, y0, x1 - x0 + 1, y1 - y0 + 1).to_image();
+
+    let target = (width.min(height) as f32 * 20.0 / 28.0).round().max(1.0);
+    let scale = target / digit.width().max(digit.height()) as f32;
+    let digit = imageops::resize(
+        &digit,
+        ((digit.width() as f32 * scale).round() as u32).max(1),
+        ((digit.height() as f32 * scale).round() as u32).max(1),
+        filter,
+    );
+
+    let (mut sum, mut sum_x, mut sum_y) = (0.0f64, 0.0f64, 0.0f64);
+    for (x, y, p) in digit.enumerate_pixels() {
+        let v = p[0] as f64;
+        sum += v;
+        sum_x += (x as f64 + 0.5) * v;
+        sum_y += (y as f64 + 0.5) * v;
+    }
+    let (com_x, com_y) = if sum > 0.0 {
+        (sum_x / sum, sum_y / sum)
+    } else {
+        (digit.width() as f64 / 2.0, digit.height() as f64 / 2.0)
+    };
+    let x = (width as f64 / 2.0 - com_x).round() as i64;
+    let y = (height as f64 / 2.0 - com_y).round() as i64;
+    imageops::replace(&mut canvas, &digit, x, y);
+    canvas
+}
+
+/// 大津の方法による二値化の閾値 (これ以下を背景とする)
+fn otsu_threshold(img: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for p in img.pixels() {
+        histogram[p[0] as usize] += 1;
+    }
+    let total = img.pixels().len() as f64;
+    let sum_all: f64 = histogram.iter().enumerate().map(|(v, &n)| v as f64 * n as f64).sum();
+
+    let (mut best, mut best_var) = (0u8, -1.0f64);
+    let (mut count_bg, mut sum_bg) = (0.0f64, 0.0f64);
+    for (v, &n) in histogram.iter().enumerate() {
+        count_bg += n as f64;
+        sum_bg += v as f64 * n as f64;
+        let count_fg = total - count_bg;
+        if count_bg == 0.0 || count_fg == 0.0 {
+            continue;
+        }
+        let diff = sum_bg / count_bg - (sum_all - sum_bg) / count_fg;
+        let var = count_bg * count_fg * diff * diff;
+        if var > best_var {
+            (best, best_var) = (v as u8, var);
+        }
+    }
+    best
+}
+
+/// 透過画像を背景色の上に合成
+fn composite(img: &DynamicImage, background: [u8; 3]) -> RgbImage {
+    let rgba = img.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let [r, g, b, a] = rgba.get_pixel(x, y).0;
+        let a = a as f32 / 255.0;
+        let blend = |fg: u8, bg: u8| (fg as f32 * a + bg as f32 * (1.0 - a)).round() as u8;
+        Rgb([blend(r, background[0]), blend(g, background[1]), blend(b, background[2])])
+    })
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use burn::backend::NdArray;
+    use image::{GrayImage, RgbImage};
+
+    type B = NdArray;
+
+    fn config(json: &str) -> DatasetConfig {
+        serde_json::from_str(json).expect("config")
+    }
+
+    fn mnist() -> DatasetConfig {
+        config(include_str!("../configs/mnist.json"))
+    }
+
+    fn cifar10() -> DatasetConfig {
+        config(include_str!("../configs/cifar10.json"))
+    }
+
+    fn assert_same(a: Tensor<B, 4>, b: Tensor<B, 4>) {
+        assert_eq!(a.dims(), b.dims());
+        a.into_data().assert_eq(&b.into_data(), true);
+    }
+
+    /// infer.rs と同じく、画像ファイルを読み込んだ後の経路
+    fn cli_path(pre: &Preprocessor, img: DynamicImage) -> Tensor<B, 4> {
+        pre.batch(pre.image_pixels(&img).unwrap(), 1, &Default::default())
+    }
+
+    /// web_mnist.rs と同じく、JS から渡される 0-255 のグレースケール画素 (H*W)
+    fn js_gray(img: &GrayImage) -> Vec<f32> {
+        img.pixels().map(|p| p[0] as f32).collect()
+    }
+
+    /// web_cifar10.rs と同じく、JS から渡される 0-255 の CHW 画素
+    fn js_rgb_chw(img: &RgbImage) -> Vec<f32> {
+        (0..3)
+            .flat_map(|c| img.pixels().map(move |p| p[c] as f32))
+            .collect()
+    }
+
+    #[test]
+    fn cli_and_wasm_paths_match_for_mnist() {
+        let pre = Preprocessor::new(&mnist()).unwrap();
+        let device = Default::default();
+        let img = GrayImage::from_fn(28, 28, |x, y| image::Luma([(x * 9 + y * 3) as u8]));
+
+        let cli = cli_path(&pre, DynamicImage::ImageLuma8(img.clone()));
+        let wasm = pre.pixels_f32::<B>(&js_gray(&img), PixelLayout::Chw, &device).unwrap();
+
+        assert_eq!(cli.dims(), [1, 1, 28, 28]);
+        assert_same(cli, wasm);
+    }
+
+    #[test]
+    fn cli_and_wasm_paths_match_for_cifar10() {
+        let pre = Preprocessor::new(&cifar10()).unwrap();
+        let device = Default::default();
+        let img = RgbImage::from_fn(32, 32, |x, y| image::Rgb([(x * 8) as u8, (y * 8) as u8, (x + y) as u8]));
+
+        let cli = cli_path(&pre, DynamicImage::ImageRgb8(img.clone()));
+        let wasm = pre.pixels_f32::<B>(&js_rgb_chw(&img), PixelLayout::Chw, &device).unwrap();
+
+        assert_eq!(cli.dims(), [1, 3, 32, 32]);
+        assert_same(cli, wasm);
+    }
+
+    #[test]
+    fn batch_matches_single_image() {
+        let pre = Preprocessor::new(&cifar10()).unwrap();
+        let device = Default::default();
+        let img = RgbImage::from_fn(32, 32, |x, y| image::Rgb([(x * y) as u8, 255 - x as u8, y as u8]));
+        let chw = js_rgb_chw(&img);
+
+        let single = pre.pixels_f32::<B>(&chw, PixelLayout::Chw, &device).unwrap();
+        let batch = pre.batch::<B>([chw.clone(), chw].concat(), 2, &device);
+
+        assert_same(single.clone(), batch.clone().narrow(0, 0, 1));
+        assert_same(single, batch.narrow(0, 1, 1));
+    }
+
+    #[test]
+    fn applies_per_channel_normalization() {
+        let pre = Preprocessor::new(&cifar10()).unwrap();
+        let device = Default::default();
+        let pixels = vec![255u8; pre.image_len()];
+
+        let t = pre.pixels_u8::<B>(&pixels, PixelLayout::Hwc, &device).unwrap();
+        let values = t.into_data().to_vec::<f32>().unwrap();
+
+        let plane = 32 * 32;
+        let expected = [
+            (1.0 - 0.4914) / 0.247,
+            (1.0 - 0.4822) / 0.2435,
+            (1.0 - 0.4465) / 0.2616,
+        ];
+        for (c, e) in expected.iter().enumerate() {
+            assert!((values[c * plane] - e).abs() < 1e-5);
+        }
+    }
+
+    /// 4x2 の RGB 画像 (左半分 = 赤, 右半分 = 緑) を 2x2 の入力に合わせた結果
+    fn fit_2x2(resize: &str, alpha: &str) -> Vec<f32> {
+        let mut cfg = cifar10();
+        cfg.input_size = [2, 2];
+        cfg.preprocessing = serde_json::from_str(&format!(
+            r#"{{"resize": "{resize}", "pad_color": [0, 0, 255], "alpha": "{alpha}", "alpha_background": [255, 255, 255]}}"#
+        ))
+        .unwrap();
+        let pre = Preprocessor::new(&cfg).unwrap();
+        let img = image::RgbaImage::from_fn(4, 2, |x, _| {
+            if x < 2 { image::Rgba([255, 0, 0, 255]) } else { image::Rgba([0, 255, 0, 0]) }
+        });
+        pre.image_pixels(&DynamicImage::ImageRgba8(img)).unwrap()
+    }
+
+    #[test]
+    fn resize_modes_follow_preprocessing_config() {
+        // CHW: [R(4画素), G(4画素), B(4画素)], 画素は左上から行順
+        assert_eq!(
+            fit_2x2("stretch", "ignore"),
+            [255., 0., 255., 0., 0., 255., 0., 255., 0., 0., 0., 0.]
+        );
+        // 4x2 -> 2x1 に縮小して上下を青で埋める
+        assert_eq!(
+            fit_2x2("letterbox", "ignore"),
+            [255., 0., 0., 0., 0., 255., 0., 0., 0., 0., 255., 255.]
+        );
+        // リサイズせず中央の 2x2 (x = 1..3) を切り出す
+        assert_eq!(
+            fit_2x2("center_crop", "ignore"),
+            [255., 0., 255., 0., 0., 255., 0., 255., 0., 0., 0., 0.]
+        );
+        // 短辺 (高さ 2) が既に合っているので中央を切り出すだけ
+        assert_eq!(fit_2x2("shorter_side_crop", "ignore"), fit_2x2("center_crop", "ignore"));
+    }
+
+    #[test]
+    fn composites_transparent_pixels_onto_background() {
+        // 右半分 (透明な緑) は白になる
+        assert_eq!(
+            fit_2x2("center_crop", "composite"),
+            [255., 255., 255., 255., 0., 255., 0., 255., 0., 255., 0., 255.]
+        );
+    }
+
+    fn canonical_mnist() -> Preprocessor {
+        let mut cfg = mnist();
+        cfg.preprocessing.resize = ResizeMode::MnistCanonicalize;
+        cfg.preprocessing.filter = ResizeFilter::Bilinear;
+        Preprocessor::new(&cfg).unwrap()
+    }
+
+    /// 白い紙 (60x40) の右上寄りに黒インクで縦長の棒 (6x24) を書いた画像
+    fn scanned_bar(ink: u8, paper: u8) -> GrayImage {
+        GrayImage::from_fn(60, 40, |x, y| {
+            image::Luma([if (40..46).contains(&x) && (4..28).contains(&y) { ink } else { paper }])
+        })
+    }
+
+    #[test]
+    fn mnist_canonicalize_centers_and_scales_the_digit() {
+        let pre = canonical_mnist();
+        let pixels = pre.image_pixels(&DynamicImage::ImageLuma8(scanned_bar(30, 230))).unwrap();
+        assert_eq!(pixels.len(), 28 * 28);
+
+        // 背景は黒、数字は白で長辺 20px
+        let rows: Vec<usize> = (0..28).filter(|y| pixels[y * 28..(y + 1) * 28].iter().any(|&p| p > 0.0)).collect();
+        let cols: Vec<usize> = (0..28).filter(|x| (0..28).any(|y| pixels[y * 28 + x] > 0.0)).collect();
+        assert_eq!(rows.len(), 20);
+        assert_eq!((rows[0], rows[19]), (4, 23));
+        assert_eq!(cols.len(), 5);
+        assert_eq!(pixels.iter().copied().fold(0.0, f32::max), 255.0);
+
+        // 重心は中央 (14, 14)
+        let sum: f32 = pixels.iter().sum();
+        let com_x: f32 = pixels.iter().enumerate().map(|(i, p)| (i % 28) as f32 * p).sum::<f32>() / sum + 0.5;
+        assert!((com_x - 14.0).abs() <= 0.5, "com_x = {com_x}");
+    }
+
+    #[test]
+    fn mnist_canonicalize_ignores_polarity_and_matches_wasm_path() {
+        let pre = canonical_mnist();
+        let device = Default::default();
+        let dark_on_light = pre.image_pixels(&DynamicImage::ImageLuma8(scanned_bar(30, 230))).unwrap();
+        let light_on_dark = pre.image_pixels(&DynamicImage::ImageLuma8(scanned_bar(225, 25))).unwrap();
+        assert_eq!(dark_on_light, light_on_dark);
+
+        // 28x28 で描かれた入力は wasm (pixels_f32) でも CLI と同じ結果になる
+        let drawn = GrayImage::from_fn(28, 28, |x, y| image::Luma([if x < 6 && y > 10 { 255 } else { 0 }]));
+        let cli = cli_path(&pre, DynamicImage::ImageLuma8(drawn.clone()));
+        let wasm = pre.pixels_f32::<B>(&js_gray(&drawn), PixelLayout::Chw, &device).unwrap();
+        assert_same(cli, wasm);
+    }
+
+    #[test]
+    fn rejects_wrong_pixel_count() {
+        let pre = Preprocessor::new(&mnist()).unwrap();
+        assert!(pre.pixels_f32::<B>(&[0.0; 10], PixelLayout::Chw, &Default::default()).is_err());
+    }
+}
@@ -0,0 +1,119 @@
+// src/registry.rs
+// `model.type` からモデル構造を選ぶ唯一の場所。新しいモデルはここに登録する。
+use crate::config::{ConfigError, DatasetConfig};
+use crate::model::{self, CifarNet, LeNet, ModelTrait, ResNet};
+use crate::sequential::{self, Sequential};
+use crate::summary::{LayerSummary, Tracer};
+use anyhow::{Result, anyhow};
+use burn::{
+    module::{AutodiffModule, Module},
+    prelude::*,
+    record::{BinBytesRecorder, CompactRecorder, FullPrecisionSettings, Recorder},
+    tensor::backend::AutodiffBackend,
+};
+
+/// 登録済みのモデルタイプ
+pub const MODEL_TYPES: &[&str] = &["lenet", "cifar_net", "resnet", "sequential"];
+
+/// 学習済みパラメータの読み込み元
+pub enum ModelSource<'a> {
+    /// `CompactRecorder` で保存したファイル (model.burn)
+    File(&'a str),
+    /// `BinBytesRecorder` で保存したバイト列 (model.bin, wasm に埋め込み)
+    #[cfg_attr(not(target_arch = "wasm32"), allow(dead_code))]
+    Bytes(&'static [u8]),
+}
+
+/// `model.type` が登録済みで、その構造が入力の大きさ・クラス数と合うか確認する (モデルは作らない)
+pub fn check_model(config: &DatasetConfig) -> Result<(), ConfigError> {
+    match config.model.model_type.as_str() {
+        "lenet" => model::check_lenet(config),
+        "cifar_net" => model::check_cifar_net(config),
+        "resnet" => model::check_resnet(config).map(|_| ()),
+        "sequential" => sequential::infer_shapes(config).map(|_| ()).map_err(|e| ConfigError::Model {
+            model_type: "sequential",
+            reason: e.to_string(),
+        }),
+        other => Err(ConfigError::UnknownModelType(other.to_string())),
+    }
+}
+
+/// 具体的なモデル型を受け取って処理する学習タスク
+pub trait TrainTask<B: AutodiffBackend> {
+    type Output;
+
+    fn run<M>(self, model: M) -> Self::Output
+    where
+        M: AutodiffModule<B> + ModelTrait<B>,
+        M::InnerModule: ModelTrait<B::InnerBackend>;
+}
+
+/// `model.type` に対応するモデルを初期化し、学習タスクに渡す
+pub fn with_trainable_model<B, T>(config: &DatasetConfig, device: &B::Device, task: T) -> Result<T::Output>
+where
+    B: AutodiffBackend,
+    T: TrainTask<B>,
+{
+    match config.model.model_type.as_str() {
+        "lenet" => Ok(task.run(LeNet::<B>::new(device, config)?)),
+        "cifar_net" => Ok(task.run(CifarNet::<B>::new(device, config)?)),
+        "resnet" => Ok(task.run(ResNet::<B>::new(device, config)?)),
+        "sequential" => Ok(task.run(Sequential::<B>::new(device, config)?)),
+        other => Err(unknown_model_type(other)),
+    }
+}
+
+/// `model.type` に対応するモデルを構築し、学習済みパラメータを読み込む
+pub fn load_model<B: Backend>(
+    config: &DatasetConfig,
+    device: &B::Device,
+    source: ModelSource,
+) -> Result<Box<dyn ModelTrait<B>>> {
+    match config.model.model_type.as_str() {
+        "lenet" => Ok(Box::new(load_record(LeNet::<B>::new(device, config)?, source, device)?)),
+        "cifar_net" => Ok(Box::new(load_record(CifarNet::<B>::new(device, config)?, source, device)?)),
+        "resnet" => Ok(Box::new(load_record(ResNet::<B>::new(device, config)?, source, device)?)),
+        "sequential" => Ok(Box::new(load_record(Sequential::<B>::new(device, config)?, source, device)?)),
+        other => Err(unknown_model_type(other)),
+    }
+}
+
+/// `model.type` に対応するモデルを構築し、各層の出力の形・パラメータ数・FLOPs と総パラメータ数を返す
+pub fn summarize<B: Backend>(config: &DatasetConfig, device: &B::Device) -> Result<(Vec<LayerSummary>, usize)> {
+    let mut tracer = Tracer::new(config);
+    let num_params = match config.model.model_type.as_str() {
+        "lenet" => summarize_model(LeNet::<B>::new(device, config)?, &mut tracer, LeNet::summary)?,
+        "cifar_net" => summarize_model(CifarNet::<B>::new(device, config)?, &mut tracer, CifarNet::summary)?,
+        "resnet" => summarize_model(ResNet::<B>::new(device, config)?, &mut tracer, ResNet::summary)?,
+        "sequential" => summarize_model(Sequential::<B>::new(device, config)?, &mut tracer, Sequential::summary)?,
+        other => return Err(unknown_model_type(other)),
+    };
+    Ok((tracer.into_layers(), num_params))
+}
+
+fn summarize_model<B: Backend, M: Module<B>>(
+    model: M,
+    tracer: &mut Tracer,
+    summary: fn(&M, &mut Tracer) -> Result<()>,
+) -> Result<usize> {
+    summary(&model, tracer)?;
+    Ok(model.num_params())
+}
+
+fn load_record<B: Backend, M: Module<B>>(model: M, source: ModelSource, device: &B::Device) -> Result<M> {
+    match source {
+        ModelSource::File(path) => model
+            .load_file(path, &CompactRecorder::new(), device)
+            .map_err(|e| anyhow!("モデル読み込み失敗: {e}")),
+        ModelSource::Bytes(bytes) => {
+            let record = BinBytesRecorder::<FullPrecisionSettings, &'static [u8]>::default()
+                .load(bytes, device)
+                .map_err(|e| anyhow!("モデル読み込み失敗: {e}"))?;
+            Ok(model.load_record(record))
+        }
+    }
+}
+
+fn unknown_model_type(model_type: &str) -> anyhow::Error {
+    ConfigError::UnknownModelType(model_type.to_string()).into()
+}
@@ -0,0 +1,395 @@
+// src/sequential.rs
+// `model.layers` (JSON) から組み立てるモデル。層の構成を変えるのに再コンパイルは不要。
+use crate::config::{ActivationKind, DatasetConfig, LayerConfig};
+use crate::model::{self, ForwardMode, ModelTrait};
+use crate::summary::Tracer;
+use anyhow::{Result, anyhow};
+use burn::nn::{
+    BatchNorm, BatchNormConfig, Dropout, DropoutConfig, Gelu, LeakyRelu, LeakyReluConfig, Linear, LinearConfig,
+    PaddingConfig2d, Relu, Sigmoid, Tanh,
+    conv::{Conv2d, Conv2dConfig},
+    pool::{AvgPool2d, AvgPool2dConfig, MaxPool2d, MaxPool2dConfig},
+};
+use burn::prelude::*;
+use std::fmt;
+
+/// 各層の出力の形 (バッチ次元を除く)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeatureShape {
+    Image { channels: usize, height: usize, width: usize },
+    Flat(usize),
+}
+
+impl fmt::Display for FeatureShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeatureShape::Image {
+                channels,
+                height,
+                width,
+            } => write!(f, "[{}, {}, {}]", channels, height, width),
+            FeatureShape::Flat(n) => write!(f, "[{}]", n),
+        }
+    }
+}
+
+/// `model.layers` の各層の出力の形を推論する (入力から順に検証し、最初の誤りでエラー)
+pub fn infer_shapes(config: &DatasetConfig) -> Result<Vec<FeatureShape>> {
+    let layers = &config.model.layers;
+    if layers.is_empty() {
+        return Err(anyhow!("sequential には model.layers が必要です"));
+    }
+
+    let mut shape = FeatureShape::Image {
+        channels: config.input_channels,
+        height: config.input_size[0],
+        width: config.input_size[1],
+    };
+    let mut shapes = Vec::with_capacity(layers.len());
+    for (i, layer) in layers.iter().enumerate() {
+        shape = output_shape(layer, shape, config.num_classes)
+            .map_err(|e| anyhow!("model.layers[{}] ({}): {} (入力 {})", i, layer.kind(), e, shape))?;
+        shapes.push(shape);
+    }
+
+    let expected = FeatureShape::Flat(config.num_classes);
+    if shape != expected {
+        return Err(anyhow!(
+            "model.layers の最後の出力が {} です ({} クラスなら {} にしてください)",
+            shape,
+            config.num_classes,
+            expected
+        ));
+    }
+    Ok(shapes)
+}
+
+fn output_shape(layer: &LayerConfig, input: FeatureShape, num_classes: usize) -> Result<FeatureShape> {
+    match (layer, input) {
+        (
+            LayerConfig::Conv {
+                out,
+                kernel,
+                stride,
+                padding,
+                ..
+            },
+            FeatureShape::Image { height, width, .. },
+        ) => {
+            if *out == 0 {
+                return Err(anyhow!("out は 1 以上にしてください"));
+            }
+            let padding = padding.unwrap_or(kernel / 2);
+            let (height, width) = window_output([height + 2 * padding, width + 2 * padding], *kernel, *stride)?;
+            Ok(FeatureShape::Image {
+                channels: *out,
+                height,
+                width,
+            })
+        }
+        (
+            LayerConfig::MaxPool { kernel, stride } | LayerConfig::AvgPool { kernel, stride },
+            FeatureShape::Image {
+                channels,
+                height,
+                width,
+            },
+        ) => {
+            let (height, width) = window_output([height, width], *kernel, stride.unwrap_or(*kernel))?;
+            Ok(FeatureShape::Image {
+                channels,
+                height,
+                width,
+            })
+        }
+        (LayerConfig::Conv { .. } | LayerConfig::MaxPool { .. } | LayerConfig::AvgPool { .. }, FeatureShape::Flat(_)) => {
+            Err(anyhow!("flatten の後には使えません"))
+        }
+        (LayerConfig::Dropout { prob }, _) if !(0.0..1.0).contains(prob) => {
+            Err(anyhow!("prob は 0 以上 1 未満にしてください: {}", prob))
+        }
+        (LayerConfig::BatchNorm | LayerConfig::Dropout { .. } | LayerConfig::Activation { .. }, _) => Ok(input),
+        (LayerConfig::Linear { out }, FeatureShape::Flat(_)) => match out.unwrap_or(num_classes) {
+            0 => Err(anyhow!("out は 1 以上にしてください")),
+            out => Ok(FeatureShape::Flat(out)),
+        },
+        (LayerConfig::Linear { .. }, FeatureShape::Image { .. }) => {
+            Err(anyhow!("画像の特徴には使えません (前に flatten を入れてください)"))
+        }
+        (
+            LayerConfig::Flatten,
+            FeatureShape::Image {
+                channels,
+                height,
+                width,
+            },
+        ) => Ok(FeatureShape::Flat(channels * height * width)),
+        (LayerConfig::Flatten, FeatureShape::Flat(_)) => Err(anyhow!("すでに flatten されています")),
+    }
+}
+
+/// 畳み込み・プーリングの出力の大きさ (縦, 横)
+fn window_output(size: [usize; 2], kernel: usize, stride: usize) -> Result<(usize, usize)> {
+    if kernel == 0 || stride == 0 {
+        return Err(anyhow!("kernel と stride は 1 以上にしてください"));
+    }
+    if size.iter().any(|&s| s < kernel) {
+        return Err(anyhow!("kernel {} が入力 {}x{} より大きいです", kernel, size[0], size[1]));
+    }
+    Ok(((size[0] - kernel) / stride + 1, (size[1] - kernel) / stride + 1))
+}
+
+#[derive(Module, Debug)]
+pub enum Layer<B: Backend> {
+    Conv(Conv2d<B>),
+    MaxPool(MaxPool2d),
+    AvgPool(AvgPool2d),
+    BatchNorm2d(BatchNorm<B, 2>),
+    BatchNorm1d(BatchNorm<B, 0>),
+    Dropout(Dropout),
+    Linear(Linear<B>),
+    Relu(Relu),
+    LeakyRelu(LeakyRelu),
+    Gelu(Gelu),
+    Tanh(Tanh),
+    Sigmoid(Sigmoid),
+    Flatten(Flatten),
+}
+
+/// [B, C, H, W] → [B, C*H*W]
+#[derive(Module, Clone, Debug)]
+pub struct Flatten;
+
+/// 層の間を流れるテンソル
+enum Features<B: Backend> {
+    Image(Tensor<B, 4>),
+    Flat(Tensor<B, 2>),
+}
+
+/// `model.layers` を順に適用するモデル
+#[derive(Module, Debug)]
+pub struct Sequential<B: Backend> {
+    layers: Vec<Layer<B>>,
+}
+
+impl<B: Backend> Sequential<B> {
+    pub fn new(device: &B::Device, config: &DatasetConfig) -> Result<Self> {
+        let shapes = infer_shapes(config)?;
+        let mut channels = config.input_channels;
+        let mut layers = Vec::with_capacity(shapes.len());
+        for (layer, shape) in config.model.layers.iter().zip(shapes) {
+            let features = match shape {
+                FeatureShape::Image { channels, .. } => channels,
+                FeatureShape::Flat(n) => n,
+            };
+            layers.push(match layer {
+                LayerConfig::Conv {
+                    out,
+                    kernel,
+                    stride,
+                    padding,
+                    bias,
+                } => {
+                    let padding = padding.unwrap_or(kernel / 2);
+                    Layer::Conv(
+                        Conv2dConfig::new([channels, *out], [*kernel, *kernel])
+                            .with_stride([*stride, *stride])
+                            .with_padding(PaddingConfig2d::Explicit(padding, padding))
+                            .with_bias(*bias)
+                            .init(device),
+                    )
+                }
+                LayerConfig::MaxPool { kernel, stride } => {
+                    let stride = stride.unwrap_or(*kernel);
+                    Layer::MaxPool(MaxPool2dConfig::new([*kernel, *kernel]).with_strides([stride, stride]).init())
+                }
+                LayerConfig::AvgPool { kernel, stride } => {
+                    let stride = stride.unwrap_or(*kernel);
+                    Layer::AvgPool(AvgPool2dConfig::new([*kernel, *kernel]).with_strides([stride, stride]).init())
+                }
+                LayerConfig::BatchNorm => match shape {
+                    FeatureShape::Image { .. } => Layer::BatchNorm2d(BatchNormConfig::new(features).init(device)),
+                    FeatureShape::Flat(_) => Layer::BatchNorm1d(BatchNormConfig::new(features).init(device)),
+                },
+                LayerConfig::Dropout { prob } => Layer::Dropout(DropoutConfig::new(*prob).init()),
+                LayerConfig::Linear { .. } => Layer::Linear(LinearConfig::new(channels, features).init(device)),
+                LayerConfig::Activation { function } => match function {
+                    ActivationKind::Relu => Layer::Relu(Relu::new()),
+                    ActivationKind::LeakyRelu => Layer::LeakyRelu(LeakyReluConfig::new().init()),
+                    ActivationKind::Gelu => Layer::Gelu(Gelu::new()),
+                    ActivationKind::Tanh => Layer::Tanh(Tanh::new()),
+                    ActivationKind::Sigmoid => Layer::Sigmoid(Sigmoid::new()),
+                },
+                LayerConfig::Flatten => Layer::Flatten(Flatten),
+            });
+            channels = features;
+        }
+        Ok(Self { layers })
+    }
+
+    pub fn summary(&self, tracer: &mut Tracer) -> Result<()> {
+        for (i, layer) in self.layers.iter().enumerate() {
+            let name = format!("layers[{}].{}", i, layer.name());
+            match layer {
+                Layer::Conv(conv) => tracer.conv(&name, conv)?,
+                Layer::MaxPool(pool) => tracer.max_pool(&name, pool)?,
+                Layer::AvgPool(pool) => tracer.avg_pool(&name, pool)?,
+                Layer::BatchNorm2d(bn) => tracer.batch_norm(&name, bn)?,
+                Layer::BatchNorm1d(bn) => tracer.batch_norm(&name, bn)?,
+                Layer::Dropout(_) => tracer.dropout(&name),
+                Layer::Linear(linear) => tracer.linear(&name, linear)?,
+                Layer::Flatten(_) => tracer.flatten(&name)?,
+                _ => tracer.activation(&name),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<B: Backend> ModelTrait<B> for Sequential<B> {
+    fn forward_mode(&self, x: Tensor<B, 4>, mode: ForwardMode) -> Tensor<B, 2> {
+        let mut x = Features::Image(x);
+        for layer in &self.layers {
+            x = layer.forward(x, mode);
+        }
+        match x {
+            Features::Flat(x) => x,
+            // infer_shapes で最後が [num_classes] であることを確認済み
+            Features::Image(_) => unreachable!("sequential の出力が flatten されていません"),
+        }
+    }
+
+    fn has_dropout(&self) -> bool {
+        self.layers
+            .iter()
+            .any(|layer| matches!(layer, Layer::Dropout(dropout) if dropout.prob > 0.0))
+    }
+}
+
+impl<B: Backend> Layer<B> {
+    fn forward(&self, x: Features<B>, mode: ForwardMode) -> Features<B> {
+        use Features::{Flat, Image};
+
+        match (self, x) {
+            (Layer::Conv(conv), Image(x)) => Image(conv.forward(x)),
+            (Layer::MaxPool(pool), Image(x)) => Image(pool.forward(x)),
+            (Layer::AvgPool(pool), Image(x)) => Image(pool.forward(x)),
+            (Layer::BatchNorm2d(bn), Image(x)) => Image(model::batch_norm(bn, x, mode)),
+            (Layer::BatchNorm1d(bn), Flat(x)) => Flat(model::batch_norm(bn, x, mode)),
+            (Layer::Linear(linear), Flat(x)) => Flat(linear.forward(x)),
+            (Layer::Flatten(_), Image(x)) => {
+                let [b, c, h, w] = x.dims();
+                Flat(x.reshape([b, c * h * w]))
+            }
+            (layer, Image(x)) => Image(layer.elementwise(x, mode)),
+            (layer, Flat(x)) => Flat(layer.elementwise(x, mode)),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Layer::Conv(_) => "conv",
+            Layer::MaxPool(_) => "max_pool",
+            Layer::AvgPool(_) => "avg_pool",
+            Layer::BatchNorm2d(_) | Layer::BatchNorm1d(_) => "batch_norm",
+            Layer::Dropout(_) => "dropout",
+            Layer::Linear(_) => "linear",
+            Layer::Relu(_) => "relu",
+            Layer::LeakyRelu(_) => "leaky_relu",
+            Layer::Gelu(_) => "gelu",
+            Layer::Tanh(_) => "tanh",
+            Layer::Sigmoid(_) => "sigmoid",
+            Layer::Flatten(_) => "flatten",
+        }
+    }
+
+    /// 形を変えない層 (ドロップアウト・活性化関数)
+    fn elementwise<const D: usize>(&self, x: Tensor<B, D>, mode: ForwardMode) -> Tensor<B, D> {
+        match self {
+            Layer::Dropout(dropout) => model::dropout(dropout, x, mode),
+            Layer::Relu(act) => act.forward(x),
+            Layer::LeakyRelu(act) => act.forward(x),
+            Layer::Gelu(act) => act.forward(x),
+            Layer::Tanh(act) => act.forward(x),
+            Layer::Sigmoid(act) => act.forward(x),
+            // infer_shapes で入力の形を確認済み
+            _ => unreachable!("{:?} は {}次元の入力に使えません", self, D),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use burn::backend::NdArray;
+    use burn::record::{BinBytesRecorder, FullPrecisionSettings, Recorder};
+
+    type B = NdArray;
+
+    fn config(layers: &str) -> DatasetConfig {
+        let mut config: DatasetConfig =
+            serde_json::from_str(include_str!("../configs/cifar10_sequential.json")).expect("config");
+        config.model.layers = serde_json::from_str(layers).expect("layers");
+        config
+    }
+
+    #[test]
+    fn infers_shapes_and_builds_model() {
+        let _rng = crate::augment::lock_backend_rng();
+        let config = config(
+            r#"[
+                {"type": "conv", "out": 8, "kernel": 3},
+                {"type": "batch_norm"},
+                {"type": "activation", "function": "relu"},
+                {"type": "max_pool", "kernel": 2},
+                {"type": "conv", "out": 4, "kernel": 5, "stride": 2, "padding": 0},
+                {"type": "flatten"},
+                {"type": "dropout", "prob": 0.5},
+                {"type": "linear", "out": 16},
+                {"type": "batch_norm"},
+                {"type": "activation", "function": "gelu"},
+                {"type": "linear"}
+            ]"#,
+        );
+        let shapes = infer_shapes(&config).unwrap();
+        assert_eq!(
+            shapes[3],
+            FeatureShape::Image {
+                channels: 8,
+                height: 16,
+                width: 16
+            }
+        );
+        assert_eq!(shapes[5], FeatureShape::Flat(4 * 6 * 6));
+        assert_eq!(shapes.last(), Some(&FeatureShape::Flat(10)));
+
+        let device = Default::default();
+        let model = Sequential::<B>::new(&device, &config).unwrap();
+        assert!(model.has_dropout());
+        let x = Tensor::<B, 4>::random([2, 3, 32, 32], burn::tensor::Distribution::Default, &device);
+        let y = model.forward(x.clone());
+        assert_eq!(y.dims(), [2, 10]);
+
+        // model.bin (BinBytesRecorder) から復元できる
+        let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+        let bytes = recorder.record(model.into_record(), ()).unwrap();
+        let loaded = Sequential::<B>::new(&device, &config)
+            .unwrap()
+            .load_record(recorder.load(bytes, &device).unwrap());
+        y.into_data().assert_eq(&loaded.forward(x).into_data(), true);
+
+        // 確率 0 の Dropout だけなら MC Dropout は毎回同じ推論になる
+        let no_dropout = self::config(r#"[{"type": "flatten"}, {"type": "dropout", "prob": 0.0}, {"type": "linear"}]"#);
+        assert!(!Sequential::<B>::new(&device, &no_dropout).unwrap().has_dropout());
+    }
+
+    #[test]
+    fn rejects_layers_that_do_not_fit_the_input() {
+        let err = |layers| infer_shapes(&config(layers)).unwrap_err().to_string();
+
+        assert!(err(r#"[{"type": "linear"}]"#).contains("model.layers[0] (linear)"));
+        assert!(err(r#"[{"type": "max_pool", "kernel": 64}, {"type": "flatten"}, {"type": "linear"}]"#).contains("kernel 64"));
+        assert!(err(r#"[{"type": "flatten"}, {"type": "linear", "out": 5}]"#).contains("最後の出力が [5]"));
+        assert!(err(r#"[{"type": "flatten"}, {"type": "conv", "out": 4, "kernel": 3}]"#).contains("flatten の後"));
+    }
+}
@@ -1,9 +1,6 @@
 use crate::config::DatasetConfig;
-use crate::model::{LeNet, CifarNet, ModelTrait};
-use burn::{
-    module::Module,
-    record::{BinBytesRecorder, FullPrecisionSettings, Recorder},
-};
+use crate::model::ModelTrait;
+use crate::registry::{self, ModelSource};
 use burn_wgpu::{WgpuDevice, graphics::AutoGraphicsApi, init_setup_async};
 type Backend = burn_wgpu::Wgpu;
 
@@ -17,19 +14,8 @@ static MNIST_CONFIG: &str = include_str!("../configs/mnist.json");
 // static CIFAR10_STATE_ENCODED: &[u8] = include_bytes!("../artifacts/cifar10/model.bin");
 // static CIFAR10_CONFIG: &str = include_str!("../configs/cifar10.json");
 
-pub enum ModelInstance {
-    Mnist(LeNet<Backend>),
-    Cifar10(CifarNet<Backend>),
-}
-
-impl ModelInstance {
-    pub fn forward(&self, x: burn::prelude::Tensor<Backend, 4>) -> burn::prelude::Tensor<Backend, 2> {
-        match self {
-            ModelInstance::Mnist(model) => model.forward(x),
-            ModelInstance::Cifar10(model) => model.forward(x),
-        }
-    }
-}
+/// `model.type` に応じてレジストリが構築したモデル
+pub type ModelInstance = Box<dyn ModelTrait<Backend>>;
 
 /// 指定されたデータセットの学習済みモデルを構築・ロード
 pub async fn build_and_load_model(dataset_name: &str) -> Result<(ModelInstance, DatasetConfig), String> {
@@ -39,14 +25,14 @@ pub async fn build_and_load_model(dataset_name: &str) -> Result<(ModelInstance,
         "mnist" => {
             let config: DatasetConfig = serde_json::from_str(MNIST_CONFIG)
                 .map_err(|e| format!("MNIST設定の解析に失敗: {}", e))?;
-                
-            let model: LeNet<Backend> = LeNet::new(&Default::default(), &config);
-            let record = BinBytesRecorder::<FullPrecisionSettings, &'static [u8]>::default()
-                .load(MNIST_STATE_ENCODED, &Default::default())
-                .map_err(|e| format!("MNISTモデルのロードに失敗: {}", e))?;
 
-            let model = model.load_record(record);
-            Ok((ModelInstance::Mnist(model), config))
+            let model = registry::load_model::<Backend>(
+                &config,
+                &Default::default(),
+                ModelSource::Bytes(MNIST_STATE_ENCODED),
+            )
+            .map_err(|e| format!("MNISTモデルのロードに失敗: {}", e))?;
+            Ok((model, config))
         },
         "cifar10" => {
             // TODO: CIFAR-10の実装
@@ -57,12 +43,8 @@ pub async fn build_and_load_model(dataset_name: &str) -> Result<(ModelInstance,
 }
 
 /// レガシーサポート: MNISTモデルのみ返す（既存のweb.rsとの互換性）
-pub async fn build_and_load_model_legacy() -> LeNet<Backend> {
+pub async fn build_and_load_model_legacy() -> ModelInstance {
     let (model, _) = build_and_load_model("mnist").await
         .expect("MNISTモデルのロードに失敗");
-    
-    match model {
-        ModelInstance::Mnist(mnist_model) => mnist_model,
-        _ => panic!("予期しないモデルタイプ")
-    }
+    model
 }
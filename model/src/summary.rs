@@ -0,0 +1,330 @@
+// src/summary.rs
+// モデルの各層の出力の形・パラメータ数・FLOPs (`summary` サブコマンド)
+use crate::config::DatasetConfig;
+use crate::sequential::FeatureShape;
+use anyhow::{Result, anyhow};
+use burn::module::Module;
+use burn::nn::{
+    BatchNorm, Linear, PaddingConfig2d,
+    conv::Conv2d,
+    pool::{AdaptiveAvgPool2d, AvgPool2d, MaxPool2d},
+};
+use burn::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 1層分の情報
+#[derive(Debug, Clone)]
+pub struct LayerSummary {
+    pub name: String,
+    pub output: FeatureShape,
+    pub params: usize,
+    /// 画像1枚あたりの浮動小数点演算数 (積和は2回と数える)
+    pub flops: u64,
+}
+
+/// 入力から順に各層を記録する (層の入力の形が前の層の出力と合わなければエラー)
+pub struct Tracer {
+    shape: FeatureShape,
+    layers: Vec<LayerSummary>,
+}
+
+impl Tracer {
+    pub fn new(config: &DatasetConfig) -> Self {
+        Self {
+            shape: FeatureShape::Image {
+                channels: config.input_channels,
+                height: config.input_size[0],
+                width: config.input_size[1],
+            },
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn shape(&self) -> FeatureShape {
+        self.shape
+    }
+
+    /// 分岐 (スキップ接続) の入力に戻す
+    pub fn set_shape(&mut self, shape: FeatureShape) {
+        self.shape = shape;
+    }
+
+    pub fn into_layers(self) -> Vec<LayerSummary> {
+        self.layers
+    }
+
+    fn push(&mut self, name: &str, output: FeatureShape, params: usize, flops: u64) {
+        self.shape = output;
+        self.layers.push(LayerSummary {
+            name: name.to_string(),
+            output,
+            params,
+            flops,
+        });
+    }
+
+    fn image(&self, name: &str) -> Result<(usize, usize, usize)> {
+        match self.shape {
+            FeatureShape::Image {
+                channels,
+                height,
+                width,
+            } => Ok((channels, height, width)),
+            FeatureShape::Flat(_) => Err(anyhow!("{}: 入力 {} は画像の形ではありません", name, self.shape)),
+        }
+    }
+
+    pub fn conv<B: Backend>(&mut self, name: &str, conv: &Conv2d<B>) -> Result<()> {
+        let (channels, height, width) = self.image(name)?;
+        let [out, in_per_group, kh, kw] = conv.weight.dims();
+        if in_per_group * conv.groups != channels {
+            return Err(anyhow!(
+                "{}: 入力 {} のチャンネル数が {} ではありません",
+                name,
+                self.shape,
+                in_per_group * conv.groups
+            ));
+        }
+        let kernel = [
+            conv.dilation[0] * (kh - 1) + 1,
+            conv.dilation[1] * (kw - 1) + 1,
+        ];
+        let padding = padding(&conv.padding.0, conv.kernel_size);
+        let (height, width) = window(name, [height, width], kernel, conv.stride, padding)?;
+        let positions = (height * width) as u64;
+        let macs = (in_per_group * kh * kw * out) as u64 * positions;
+        let bias = if conv.bias.is_some() { out as u64 * positions } else { 0 };
+        self.push(
+            name,
+            FeatureShape::Image {
+                channels: out,
+                height,
+                width,
+            },
+            conv.num_params(),
+            2 * macs + bias,
+        );
+        Ok(())
+    }
+
+    pub fn max_pool(&mut self, name: &str, pool: &MaxPool2d) -> Result<()> {
+        self.pool(name, pool.kernel_size, pool.stride, padding(&pool.padding.0, pool.kernel_size))
+    }
+
+    pub fn avg_pool(&mut self, name: &str, pool: &AvgPool2d) -> Result<()> {
+        self.pool(name, pool.kernel_size, pool.stride, padding(&pool.padding.0, pool.kernel_size))
+    }
+
+    fn pool(&mut self, name: &str, kernel: [usize; 2], stride: [usize; 2], padding: [usize; 2]) -> Result<()> {
+        let (channels, height, width) = self.image(name)?;
+        let (height, width) = window(name, [height, width], kernel, stride, padding)?;
+        let output = FeatureShape::Image {
+            channels,
+            height,
+            width,
+        };
+        self.push(name, output, 0, elements(output) * (kernel[0] * kernel[1]) as u64);
+        Ok(())
+    }
+
+    pub fn adaptive_avg_pool(&mut self, name: &str, pool: &AdaptiveAvgPool2d) -> Result<()> {
+        let (channels, _, _) = self.image(name)?;
+        let [height, width] = pool.output_size;
+        let flops = elements(self.shape);
+        self.push(
+            name,
+            FeatureShape::Image {
+                channels,
+                height,
+                width,
+            },
+            0,
+            flops,
+        );
+        Ok(())
+    }
+
+    pub fn batch_norm<B: Backend, const D: usize>(&mut self, name: &str, bn: &BatchNorm<B, D>) -> Result<()> {
+        let [features] = bn.gamma.dims();
+        let channels = match self.shape {
+            FeatureShape::Image { channels, .. } => channels,
+            FeatureShape::Flat(n) => n,
+        };
+        if channels != features {
+            return Err(anyhow!("{}: 入力 {} の特徴数が {} ではありません", name, self.shape, features));
+        }
+        // 推論時はチャンネルごとの乗算と加算
+        self.push(name, self.shape, bn.num_params(), 2 * elements(self.shape));
+        Ok(())
+    }
+
+    pub fn linear<B: Backend>(&mut self, name: &str, linear: &Linear<B>) -> Result<()> {
+        let [d_input, d_output] = linear.weight.dims();
+        if self.shape != FeatureShape::Flat(d_input) {
+            return Err(anyhow!("{}: 入力 {} ですが [{}] を受け取る層です", name, self.shape, d_input));
+        }
+        let bias = if linear.bias.is_some() { d_output as u64 } else { 0 };
+        self.push(
+            name,
+            FeatureShape::Flat(d_output),
+            linear.num_params(),
+            2 * (d_input * d_output) as u64 + bias,
+        );
+        Ok(())
+    }
+
+    pub fn flatten(&mut self, name: &str) -> Result<()> {
+        let (channels, height, width) = self.image(name)?;
+        self.push(name, FeatureShape::Flat(channels * height * width), 0, 0);
+        Ok(())
+    }
+
+    /// 活性化関数 (要素ごとに1回)
+    pub fn activation(&mut self, name: &str) {
+        self.push(name, self.shape, 0, elements(self.shape));
+    }
+
+    /// ドロップアウト (推論時は何もしない)
+    pub fn dropout(&mut self, name: &str) {
+        self.push(name, self.shape, 0, 0);
+    }
+
+    /// スキップ接続の加算 (`other` は分岐したもう一方の出力)
+    pub fn add(&mut self, name: &str, other: FeatureShape) -> Result<()> {
+        if self.shape != other {
+            return Err(anyhow!("{}: 足し合わせる形が一致しません: {} と {}", name, self.shape, other));
+        }
+        self.push(name, self.shape, 0, elements(self.shape));
+        Ok(())
+    }
+}
+
+fn elements(shape: FeatureShape) -> u64 {
+    match shape {
+        FeatureShape::Image {
+            channels,
+            height,
+            width,
+        } => (channels * height * width) as u64,
+        FeatureShape::Flat(n) => n as u64,
+    }
+}
+
+fn padding(padding: &PaddingConfig2d, kernel: [usize; 2]) -> [usize; 2] {
+    match padding {
+        PaddingConfig2d::Valid => [0, 0],
+        PaddingConfig2d::Same => [(kernel[0] - 1) / 2, (kernel[1] - 1) / 2],
+        PaddingConfig2d::Explicit(h, w) => [*h, *w],
+    }
+}
+
+fn window(name: &str, size: [usize; 2], kernel: [usize; 2], stride: [usize; 2], padding: [usize; 2]) -> Result<(usize, usize)> {
+    let [h, w] = [size[0] + 2 * padding[0], size[1] + 2 * padding[1]];
+    if h < kernel[0] || w < kernel[1] {
+        return Err(anyhow!(
+            "{}: 入力 {}x{} が kernel {}x{} より小さいです",
+            name,
+            size[0],
+            size[1],
+            kernel[0],
+            kernel[1]
+        ));
+    }
+    Ok(((h - kernel[0]) / stride[0] + 1, (w - kernel[1]) / stride[1] + 1))
+}
+
+/// 層の一覧と合計を表示する
+pub fn print_summary(config: &DatasetConfig, layers: &[LayerSummary], total_params: usize) {
+    let name_width = layers.iter().map(|l| l.name.len()).max().unwrap_or(0).max("layer".len());
+    let input = FeatureShape::Image {
+        channels: config.input_channels,
+        height: config.input_size[0],
+        width: config.input_size[1],
+    };
+    println!("model: {} | dataset: {} | input: {}", config.model.model_type, config.name, input);
+    println!();
+    println!("{:<name_width$} | {:<16} | {:>10} | {:>10}", "layer", "output", "params", "FLOPs");
+    println!("{}", "-".repeat(name_width + 45));
+    for layer in layers {
+        println!(
+            "{:<name_width$} | {:<16} | {:>10} | {:>10}",
+            layer.name,
+            layer.output.to_string(),
+            layer.params,
+            human(layer.flops as f64, "")
+        );
+    }
+    println!("{}", "-".repeat(name_width + 45));
+
+    let flops: u64 = layers.iter().map(|l| l.flops).sum();
+    println!(
+        "total params: {} ({} as f32)",
+        total_params,
+        human(total_params as f64 * 4.0, "B")
+    );
+    println!("total FLOPs : {} / image", human(flops as f64, ""));
+}
+
+/// 1234567 -> "1.23M"
+pub fn human(value: f64, unit: &str) -> String {
+    const PREFIXES: [&str; 4] = ["", "K", "M", "G"];
+    let mut value = value;
+    let mut prefix = 0;
+    while value >= 1000.0 && prefix < PREFIXES.len() - 1 {
+        value /= 1000.0;
+        prefix += 1;
+    }
+    if prefix == 0 {
+        format!("{}{}", value, unit)
+    } else {
+        format!("{:.2}{}{}", value, PREFIXES[prefix], unit)
+    }
+}
+
+/// 学習済みファイルの大きさを表示する (まだなければその旨)
+pub fn print_artifacts(config: &DatasetConfig) {
+    let model_path = config.get_model_path();
+    // CompactRecorder は拡張子を .mpk に置き換えて保存する
+    let mpk_path = Path::new(&model_path).with_extension("mpk");
+    print_file_size(&config.artifacts.model_file, &[PathBuf::from(&model_path), mpk_path]);
+    print_file_size(&config.artifacts.model_bin, &[PathBuf::from(config.get_model_bin_path())]);
+}
+
+fn print_file_size(label: &str, candidates: &[PathBuf]) {
+    match candidates
+        .iter()
+        .find_map(|path| fs::metadata(path).ok().map(|meta| (path, meta.len())))
+    {
+        Some((path, len)) => println!("{}: {} ({})", label, path.display(), human(len as f64, "B")),
+        None => println!("{}: なし ({} は未学習)", label, candidates[0].display()),
+    }
+}
+
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use crate::registry;
+    use burn::backend::NdArray;
+
+    type B = NdArray;
+
+    #[test]
+    fn summary_adds_up_and_rejects_inconsistent_input_size() {
+        let _rng = crate::augment::lock_backend_rng();
+        let device = Default::default();
+        let mut config: DatasetConfig = serde_json::from_str(include_str!("../configs/cifar10_resnet20.json")).expect("config");
+        config.model.depth = Some(8);
+        config.model.base_width = Some(4);
+        let (layers, num_params) = registry::summarize::<B>(&config, &device).unwrap();
+        assert_eq!(layers.iter().map(|l| l.params).sum::<usize>(), num_params);
+        assert_eq!(layers.last().unwrap().output, FeatureShape::Flat(10));
+        // stem conv: 3x3x3 -> 4ch, 32x32 の各位置で積和
+        assert_eq!(layers[0].flops, 2 * 3 * 9 * 4 * 32 * 32);
+
+        let mut config: DatasetConfig = serde_json::from_str(include_str!("../configs/mnist.json")).expect("config");
+        config.input_size = [30, 30];
+        let err = registry::summarize::<B>(&config, &device).unwrap_err();
+        assert!(err.to_string().contains("4 の倍数"), "{err}");
+    }
+}
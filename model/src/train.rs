@@ -1,247 +1,601 @@
 // src/train.rs
-use crate::config::DatasetConfig;
-use crate::data::{Cifar10Dataset, CifarBatch, CifarBatcher, MnistBatch, MnistBatcher};
-use crate::model::{CifarNet, LeNet, ModelTrait};
-use anyhow::Result;
-use burn::tensor::backend::AutodiffBackend;
+use crate::checkpoint::{Checkpointer, TrainState};
+use crate::config::{DatasetConfig, DatasetSource, OptimizerConfig};
+use crate::data::{self, CifarBatcher, CifarDataset, ImageBatch, ImageFolderBatcher, MnistBatcher};
+use crate::image_folder::{ImageFolder, Split};
+use crate::lr_schedule::LrScheduler;
+use crate::metrics::{MetricsLogger, MetricsRecord};
+use crate::model::{ForwardMode, ModelTrait};
+use crate::preprocess::Preprocessor;
+use crate::registry::{self, TrainTask};
+use anyhow::{Result, anyhow};
 use burn::{
     backend::Autodiff,
-    data::{dataloader::DataLoaderBuilder, dataset::vision::MnistDataset},
+    data::{
+        dataloader::{DataLoader, DataLoaderBuilder, DataLoaderIterator, batcher::Batcher},
+        dataset::Dataset,
+    },
     module::AutodiffModule,
     nn::loss::CrossEntropyLossConfig,
-    optim::{AdamConfig, GradientsParams, Optimizer},
+    optim::{
+        AdamConfig, AdamWConfig, GradientsParams, Optimizer, SgdConfig, decay::WeightDecayConfig,
+        momentum::MomentumConfig,
+    },
     prelude::*,
     record::{BinBytesRecorder, CompactRecorder, FullPrecisionSettings, Recorder},
 };
-// CIFAR-10データセット用のカスタム実装を使用
-use burn_wgpu::WgpuDevice;
-use std::fs;
-type B = burn_wgpu::Wgpu;
+use std::{fmt::Debug, fs, path::Path, sync::Arc, time::Instant};
+
+/// 学習時の乱数シード (CPUバックエンドで同じ設定なら同じ結果になるように固定)
+const SEED: u64 = 42;
 
 pub struct TrainConfig {
     pub dataset_config: DatasetConfig,
     pub epochs: u32,
     pub batch_size: usize,
+    /// 再開するチェックポイント ("latest" なら最新)
+    pub resume: Option<String>,
 }
 
-pub fn train(cfg: TrainConfig) -> Result<()> {
-    let device = WgpuDevice::default();
+pub fn train<B: Backend>(cfg: TrainConfig, device: B::Device) -> Result<()> {
+    B::seed(SEED);
 
     // アーティファクトディレクトリを作成
     fs::create_dir_all(&cfg.dataset_config.artifacts.dir)?;
 
-    // データセット & ローダーをデータセットタイプに応じて作成
-    match cfg.dataset_config.name.as_str() {
-        "mnist" => train_mnist(cfg, device),
-        "cifar10" => train_cifar10(cfg, device),
-        _ => Err(anyhow::anyhow!(
-            "未対応のデータセット: {}",
-            cfg.dataset_config.name
-        )),
+    // データセット & バッチャーをデータセットタイプに応じて選択
+    // (組み込みデータセットは学習データから検証用を切り出す)
+    let config = &cfg.dataset_config;
+    let validation = &config.training.validation;
+    match (&config.source, config.name.as_str()) {
+        (DatasetSource::Builtin, "mnist") | (DatasetSource::Idx { .. }, _) => {
+            let (train_ds, val_ds) = data::split_validation(data::mnist_family(config, true)?, validation)?;
+            fit::<B, _, _>(
+                &cfg,
+                &device,
+                MnistBatcher::new(config)?.with_augmentation(config),
+                MnistBatcher::new(config)?,
+                train_ds,
+                val_ds,
+                data::mnist_family(config, false)?,
+            )
+        }
+        (DatasetSource::Cifar10 { dir, synthetic }, _) => {
+            let (train_ds, val_ds) = data::split_validation(CifarDataset::cifar10(dir, *synthetic, true)?, validation)?;
+            fit::<B, _, _>(
+                &cfg,
+                &device,
+                CifarBatcher::new(config)?.with_augmentation(config),
+                CifarBatcher::new(config)?,
+                train_ds,
+                val_ds,
+                CifarDataset::cifar10(dir, *synthetic, false)?,
+            )
+        }
+        (DatasetSource::Cifar100 { dir, label }, _) => {
+            let (train_ds, val_ds) = data::split_validation(CifarDataset::cifar100(dir, *label, true)?, validation)?;
+            fit::<B, _, _>(
+                &cfg,
+                &device,
+                CifarBatcher::new(config)?.with_augmentation(config),
+                CifarBatcher::new(config)?,
+                train_ds,
+                val_ds,
+                CifarDataset::cifar100(dir, *label, false)?,
+            )
+        }
+        (DatasetSource::Builtin, name) => Err(anyhow!("未対応のデータセット: {}", name)),
+        (DatasetSource::ImageFolder { .. }, _) => {
+            let folder = ImageFolder::scan(config)?;
+            let preprocessor = Preprocessor::new(config)?;
+            fit::<B, _, _>(
+                &cfg,
+                &device,
+                ImageFolderBatcher::new(config)?.with_augmentation(config),
+                ImageFolderBatcher::new(config)?,
+                folder.dataset(Split::Train, &preprocessor)?,
+                folder.dataset(Split::Val, &preprocessor)?,
+                folder.dataset(Split::Test, &preprocessor)?,
+            )
+        }
     }
 }
 
-fn train_mnist(cfg: TrainConfig, device: WgpuDevice) -> Result<()> {
-    // データセット & ローダー
-    let train_ds = MnistDataset::train();
-    let test_ds = MnistDataset::test();
-    let batcher = MnistBatcher::default();
-
-    let train_loader = DataLoaderBuilder::new(batcher.clone())
+/// ローダーを作成し、`model.type` に対応するモデルで学習する
+fn fit<B, I, O>(
+    cfg: &TrainConfig,
+    device: &B::Device,
+    train_batcher: impl Batcher<B, I, O> + 'static,
+    eval_batcher: impl Batcher<B, I, O> + Clone + 'static,
+    train_ds: impl Dataset<I> + 'static,
+    val_ds: impl Dataset<I> + 'static,
+    test_ds: impl Dataset<I> + 'static,
+) -> Result<()>
+where
+    B: Backend,
+    I: Send + Sync + Clone + Debug + 'static,
+    O: ImageBatch<B> + Clone + Send + Debug + 'static,
+{
+    // 毎エポックの評価は検証用データで行い、テストデータは最後に1回だけ使う
+    println!(
+        "Split: train {} / val {} / test {} samples",
+        train_ds.len(),
+        val_ds.len(),
+        test_ds.len()
+    );
+
+    // バッチは推論バックエンド上に作り、学習時に Autodiff バックエンドへ持ち上げる
+    // (データ拡張は学習用バッチャーのみ)
+    let train_loader = DataLoaderBuilder::new(train_batcher)
         .batch_size(cfg.batch_size)
-        .shuffle(42)
+        .shuffle(SEED)
+        .set_device(device.clone())
         .build(train_ds);
 
-    let test_loader = DataLoaderBuilder::new(batcher)
+    let val_loader = DataLoaderBuilder::new(eval_batcher.clone())
         .batch_size(cfg.batch_size)
+        .set_device(device.clone())
+        .build(val_ds);
+
+    let test_loader = DataLoaderBuilder::new(eval_batcher)
+        .batch_size(cfg.batch_size)
+        .set_device(device.clone())
         .build(test_ds);
 
-    // モデル & オプティマイザ（Autodiff バックエンドで）
-    type AD = Autodiff<B>;
-    let device_ad = device.clone().into();
-    let mut model = LeNet::<AD>::new(&device_ad, &cfg.dataset_config);
-    let mut optim = AdamConfig::new().init();
+    let trainer = Trainer {
+        cfg,
+        device,
+        train_loader,
+        val_loader,
+        test_loader,
+    };
+    registry::with_trainable_model::<Autodiff<B>, _>(&cfg.dataset_config, device, trainer)?
+}
 
-    let ce = CrossEntropyLossConfig::new().init(&device_ad);
+/// 全データセット・全モデル共通の学習ループ
+struct Trainer<'a, B: Backend, O> {
+    cfg: &'a TrainConfig,
+    device: &'a B::Device,
+    train_loader: Arc<dyn DataLoader<B, O>>,
+    val_loader: Arc<dyn DataLoader<B, O>>,
+    test_loader: Arc<dyn DataLoader<B, O>>,
+}
 
-    for epoch in 1..=cfg.epochs {
-        // ===== Train =====
-        let mut running_loss = 0.0f32;
-        let mut n = 0usize;
+impl<B, O> TrainTask<Autodiff<B>> for Trainer<'_, B, O>
+where
+    B: Backend,
+    O: ImageBatch<B>,
+{
+    type Output = Result<()>;
+
+    fn run<M>(self, model: M) -> Result<()>
+    where
+        M: AutodiffModule<Autodiff<B>> + ModelTrait<Autodiff<B>>,
+        M::InnerModule: ModelTrait<B>,
+    {
+        // オプティマイザ（Autodiff バックエンドで）
+        match self.cfg.dataset_config.training.optimizer {
+            OptimizerConfig::Adam { weight_decay } => {
+                let optim = AdamConfig::new()
+                    .with_weight_decay(weight_decay.map(WeightDecayConfig::new))
+                    .init();
+                self.train_with(model, optim)
+            }
+            OptimizerConfig::AdamW { weight_decay } => {
+                let optim = AdamWConfig::new().with_weight_decay(weight_decay).init();
+                self.train_with(model, optim)
+            }
+            OptimizerConfig::Sgd {
+                momentum,
+                dampening,
+                nesterov,
+                weight_decay,
+            } => {
+                let momentum = (momentum > 0.0).then(|| {
+                    MomentumConfig::new()
+                        .with_momentum(momentum)
+                        .with_dampening(dampening)
+                        .with_nesterov(nesterov)
+                });
+                let optim = SgdConfig::new()
+                    .with_momentum(momentum)
+                    .with_weight_decay(weight_decay.map(WeightDecayConfig::new))
+                    .init();
+                self.train_with(model, optim)
+            }
+        }
+    }
+}
 
-        for batch in train_loader.iter() {
-            let images = batch.images.to_device(&device_ad).require_grad();
-            let targets = batch.targets.to_device(&device_ad);
+impl<B, O> Trainer<'_, B, O>
+where
+    B: Backend,
+    O: ImageBatch<B>,
+{
+    fn train_with<M, Opt>(self, mut model: M, mut optim: Opt) -> Result<()>
+    where
+        M: AutodiffModule<Autodiff<B>> + ModelTrait<Autodiff<B>>,
+        M::InnerModule: ModelTrait<B>,
+        Opt: Optimizer<M, Autodiff<B>>,
+    {
+        let cfg = self.cfg;
+        let device = self.device;
+        let train_loader = self.train_loader;
+        let val_loader = self.val_loader;
+        let test_loader = self.test_loader;
+
+        let training = &cfg.dataset_config.training;
+        let steps_per_epoch = train_loader.num_items().div_ceil(cfg.batch_size.max(1));
+        let scheduler = LrScheduler::new(
+            training.learning_rate,
+            &training.lr_scheduler,
+            cfg.epochs,
+            steps_per_epoch,
+        );
 
-            let logits = model.forward(images);
-            let loss = ce.forward(logits.clone(), targets.clone());
+        // チェックポイントから再開
+        let checkpointer = Checkpointer::new(&cfg.dataset_config);
+        let mut state = TrainState::new(SEED);
+        // これまでで最良のエポックの重み (チェックポイントにも保存する)
+        let mut best_model: Option<M::InnerModule> = None;
+        if let Some(resume) = &cfg.resume {
+            let path = checkpointer.resolve(resume)?;
+            (model, optim, state) = checkpointer.load(&path, model, optim, device)?;
+            println!(
+                "Resumed: {} (epoch {}, step {})",
+                path.display(),
+                state.epoch,
+                state.global_step
+            );
+            for discarded in checkpointer.discard_after(state.global_step)? {
+                println!("Discarded: {} (再開点より後のチェックポイント)", discarded.display());
+            }
+            best_model = restore_best_model(&cfg.dataset_config, &checkpointer, &path, &state, model.valid(), device)?;
+        }
+        // 保存時点の乱数状態を復元 (シャッフル順は完了済みエポック数ぶんローダーを進める)
+        B::seed(state.rng_seed());
+        for _ in 1..state.epoch {
+            drop(train_loader.iter());
+        }
 
-            let grads = loss.backward();
-            let grads_params = GradientsParams::from_grads::<AD, _>(grads, &model);
-            model = optim.step(
-                cfg.dataset_config.training.learning_rate,
-                model,
-                grads_params,
+        let mut metrics = MetricsLogger::create(
+            &cfg.dataset_config,
+            cfg.resume.is_some().then_some(state.global_step),
+            state.elapsed_secs,
+        )?;
+        metrics.log(&MetricsRecord::Run {
+            dataset: cfg.dataset_config.name.clone(),
+            model: cfg.dataset_config.model.model_type.clone(),
+            optimizer: training.optimizer.clone(),
+            lr_scheduler: training.lr_scheduler.clone(),
+            epochs: cfg.epochs,
+            batch_size: cfg.batch_size,
+            learning_rate: training.learning_rate,
+            resumed_from: cfg.resume.is_some().then_some(state.global_step),
+        })?;
+
+        let ce = CrossEntropyLossConfig::new().init(device);
+
+        let early_stopping = &training.early_stopping;
+        let mut lr = scheduler.lr(state.global_step);
+        for epoch in state.epoch..=cfg.epochs {
+            if state.should_stop(early_stopping.patience) {
+                break;
+            }
+
+            // ===== Train =====
+            let epoch_start = Instant::now();
+            let mut epoch_images = 0usize;
+            let mut step_start = Instant::now();
+            for batch in train_batches(train_loader.as_ref(), &state) {
+                let batch_len = batch.targets().dims()[0];
+                let images = Tensor::<Autodiff<B>, 4>::from_inner(batch.images().clone());
+                let targets = Tensor::<Autodiff<B>, 1, Int>::from_inner(batch.targets().clone());
+
+                let logits = model.forward_mode(images, ForwardMode::Train);
+                let loss = ce.forward(logits, targets);
+
+                let grads = loss.backward();
+                let grads_params = GradientsParams::from_grads::<Autodiff<B>, _>(grads, &model);
+                lr = scheduler.lr(state.global_step);
+                model = optim.step(lr, model, grads_params);
+                let loss_value = loss.into_data().to_vec::<f32>().expect("loss value")[0];
+                state.step(loss_value);
+
+                epoch_images += batch_len;
+                metrics.log(&MetricsRecord::Step {
+                    epoch,
+                    step: state.global_step,
+                    loss: loss_value,
+                    lr,
+                    images_per_sec: batch_len as f64 / step_start.elapsed().as_secs_f64(),
+                    elapsed_secs: metrics.elapsed_secs(),
+                })?;
+                step_start = Instant::now();
+
+                if checkpointer.should_save_step(state.global_step) {
+                    state.elapsed_secs = metrics.elapsed_secs();
+                    checkpointer.save(&model, best_model.as_ref(), &optim, &state)?;
+                    B::seed(state.rng_seed());
+                }
+            }
+
+            let images_per_sec = epoch_images as f64 / epoch_start.elapsed().as_secs_f64();
+
+            // ===== Validation =====
+            let (val_acc, val_loss, count) = evaluate(&model.valid(), &val_loader, device);
+            let train_loss = state.train_loss();
+            println!(
+                "epoch {epoch:02} | lr {lr:.3e} | train_loss {train_loss:.4} | val_loss {val_loss:.4} | val_acc {:.2}% ({count} samples) | {images_per_sec:.0} img/s",
+                val_acc * 100.0
             );
-            let loss_value = loss.into_data().to_vec::<f32>().expect("loss value")[0];
-            running_loss += loss_value;
-            n += 1;
+
+            // 最良のエポックなら最終モデルとして保存
+            let score = early_stopping.monitor.score(val_acc, val_loss);
+            if state.finish_epoch(score, early_stopping.min_delta) {
+                println!("Best: epoch {epoch:02}");
+                let best = model.valid();
+                save_model(&cfg.dataset_config, best.clone())?;
+                best_model = Some(best);
+            }
+
+            state.elapsed_secs = metrics.elapsed_secs();
+            metrics.log(&MetricsRecord::Epoch {
+                epoch,
+                step: state.global_step,
+                train_loss,
+                val_loss,
+                val_acc,
+                lr,
+                images_per_sec,
+                elapsed_secs: state.elapsed_secs,
+            })?;
+            let path = checkpointer.save(&model, best_model.as_ref(), &optim, &state)?;
+            B::seed(state.rng_seed());
+            println!("Checkpoint: {}", path.display());
+
+            if state.should_stop(early_stopping.patience) {
+                println!(
+                    "Early stopping: {} epochs without improvement (best epoch {:02})",
+                    state.epochs_since_best, state.best_epoch
+                );
+                break;
+            }
         }
 
-        // ===== Eval =====
-        let (acc, count) = evaluate_mnist::<B, AD>(&model, &test_loader, &device);
+        // ===== Test (最良モデルで1回だけ) =====
+        let best_model: M::InnerModule = if state.best_epoch > 0 {
+            model
+                .valid()
+                .load_file(cfg.dataset_config.get_model_path(), &CompactRecorder::new(), device)
+                .map_err(|e| anyhow!("最良モデルの読み込み失敗: {e}"))?
+        } else {
+            let base_model = model.valid();
+            save_model(&cfg.dataset_config, base_model.clone())?;
+            base_model
+        };
+        let (test_acc, test_loss, count) = evaluate(&best_model, &test_loader, device);
         println!(
-            "epoch {epoch:02} | train_loss {:.4} | test_acc {:.2}% ({count} samples)",
-            running_loss / (n.max(1) as f32),
-            acc * 100.0
+            "test (best epoch {:02}) | test_loss {test_loss:.4} | test_acc {:.2}% ({count} samples)",
+            state.best_epoch,
+            test_acc * 100.0
         );
+        metrics.log(&MetricsRecord::Test {
+            best_epoch: state.best_epoch,
+            test_loss,
+            test_acc,
+            samples: count,
+        })?;
+        Ok(())
     }
-
-    // 保存
-    let base_model: LeNet<B> = model.clone().valid();
-    let model_path = cfg.dataset_config.get_model_path();
-    let bin_path = cfg.dataset_config.get_model_bin_path();
-
-    base_model
-        .clone()
-        .save_file(&model_path, &CompactRecorder::new())
-        .expect("save");
-    println!("Saved: {}", model_path);
-
-    let record = base_model.clone().into_record();
-    let bytes: Vec<u8> = BinBytesRecorder::<FullPrecisionSettings, Vec<u8>>::default()
-        .record(record, ())
-        .expect("serialize bin bytes");
-    fs::write(&bin_path, &bytes).expect("write model.bin");
-    println!("Saved: {} ({} bytes)", bin_path, bytes.len());
-    Ok(())
 }
 
-fn train_cifar10(cfg: TrainConfig, device: WgpuDevice) -> Result<()> {
-    // データセット & ローダー
-    let train_ds = Cifar10Dataset::train();
-    let test_ds = Cifar10Dataset::test();
-    let batcher = CifarBatcher::default();
-
-    let train_loader = DataLoaderBuilder::new(batcher.clone())
-        .batch_size(cfg.batch_size)
-        .shuffle(42)
-        .build(train_ds);
-
-    let test_loader = DataLoaderBuilder::new(batcher)
-        .batch_size(cfg.batch_size)
-        .build(test_ds);
-
-    // モデル & オプティマイザ（Autodiff バックエンドで）
-    type AD = Autodiff<B>;
-    let device_ad = device.clone().into();
-    let mut model = CifarNet::<AD>::new(&device_ad, &cfg.dataset_config);
-    let mut optim = AdamConfig::new().init();
-
-    let ce = CrossEntropyLossConfig::new().init(&device_ad);
-
-    for epoch in 1..=cfg.epochs {
-        // ===== Train =====
-        let mut running_loss = 0.0f32;
-        let mut n = 0usize;
-
-        for batch in train_loader.iter() {
-            let images = batch.images.to_device(&device_ad).require_grad();
-            let targets = batch.targets.to_device(&device_ad);
-
-            let logits = model.forward(images);
-            let loss = ce.forward(logits.clone(), targets.clone());
-
-            let grads = loss.backward();
-            let grads_params = GradientsParams::from_grads::<AD, _>(grads, &model);
-            model = optim.step(
-                cfg.dataset_config.training.learning_rate,
-                model,
-                grads_params,
-            );
-            let loss_value = loss.into_data().to_vec::<f32>().expect("loss value")[0];
-            running_loss += loss_value;
-            n += 1;
+/// エポックの学習バッチ (エポック途中から再開したときは完了済みのステップを読み飛ばす)
+///
+/// 読み飛ばすバッチもデータ拡張でバックエンドの乱数を引くので、
+/// 読み飛ばした後で保存時点の乱数状態 (`rng_seed`) に戻す。
+fn train_batches<'a, B: Backend, O>(
+    loader: &'a dyn DataLoader<B, O>,
+    state: &TrainState,
+) -> Box<dyn DataLoaderIterator<O> + 'a> {
+    let mut batches = loader.iter();
+    if state.step_in_epoch > 0 {
+        for _ in 0..state.step_in_epoch {
+            if batches.next().is_none() {
+                break;
+            }
         }
+        B::seed(state.rng_seed());
+    }
+    batches
+}
 
-        // ===== Eval =====
-        let (acc, count) = evaluate_cifar10::<B, AD>(&model, &test_loader, &device);
-        println!(
-            "epoch {epoch:02} | train_loss {:.4} | test_acc {:.2}% ({count} samples)",
-            running_loss / (n.max(1) as f32),
-            acc * 100.0
-        );
+/// 再開するチェックポイントの最良モデルで model.burn / model.bin を書き直す
+///
+/// 放棄した実行の後半で最良になったエポックのモデルが残っていると、
+/// 再開後にそれを上回らなければ最終テストと最終モデルがそのモデルになってしまう。
+fn restore_best_model<B: Backend, M: Module<B>>(
+    config: &DatasetConfig,
+    checkpointer: &Checkpointer,
+    path: &Path,
+    state: &TrainState,
+    model: M,
+    device: &B::Device,
+) -> Result<Option<M>> {
+    let best = checkpointer.load_best(path, model, device)?;
+    match &best {
+        Some(best) => save_model(config, best.clone())?,
+        None if state.best_epoch > 0 => eprintln!(
+            "(warn) チェックポイントに最良モデル (epoch {:02}) がありません: {} は再開前の実行のものです",
+            state.best_epoch,
+            config.get_model_path()
+        ),
+        None => {}
     }
+    Ok(best)
+}
 
-    // 保存
-    let base_model: CifarNet<B> = model.clone().valid();
-    let model_path = cfg.dataset_config.get_model_path();
-    let bin_path = cfg.dataset_config.get_model_bin_path();
+/// 推論用の model.burn と wasm 用の model.bin を保存
+fn save_model<B: Backend, M: Module<B>>(config: &DatasetConfig, model: M) -> Result<()> {
+    let model_path = config.get_model_path();
+    let bin_path = config.get_model_bin_path();
 
-    base_model
+    model
         .clone()
         .save_file(&model_path, &CompactRecorder::new())
-        .expect("save");
+        .map_err(|e| anyhow!("モデルの保存失敗 {}: {}", model_path, e))?;
     println!("Saved: {}", model_path);
 
-    let record = base_model.clone().into_record();
     let bytes: Vec<u8> = BinBytesRecorder::<FullPrecisionSettings, Vec<u8>>::default()
-        .record(record, ())
-        .expect("serialize bin bytes");
-    fs::write(&bin_path, &bytes).expect("write model.bin");
+        .record(model.into_record(), ())
+        .map_err(|e| anyhow!("model.bin のシリアライズ失敗: {e}"))?;
+    fs::write(&bin_path, &bytes).map_err(|e| anyhow!("モデルの保存失敗 {}: {}", bin_path, e))?;
     println!("Saved: {} ({} bytes)", bin_path, bytes.len());
     Ok(())
 }
 
-fn evaluate_mnist<Bx, ADx>(
-    model_ad: &LeNet<ADx>,
-    loader: &std::sync::Arc<dyn burn::data::dataloader::DataLoader<Bx, MnistBatch<Bx>>>,
-    device: &Bx::Device,
-) -> (f32, usize)
+/// 正解率・平均損失と件数
+fn evaluate<B, M, O>(model: &M, loader: &Arc<dyn DataLoader<B, O>>, device: &B::Device) -> (f32, f32, usize)
 where
-    Bx: Backend,
-    ADx: AutodiffBackend<InnerBackend = Bx>,
+    B: Backend,
+    M: ModelTrait<B>,
+    O: ImageBatch<B>,
 {
-    let model_eval: LeNet<Bx> = model_ad.clone().valid();
-
+    let ce = CrossEntropyLossConfig::new().init(device);
     let mut correct = 0usize;
     let mut total = 0usize;
+    let mut loss_sum = 0.0f32;
 
     for batch in loader.iter() {
-        let logits = model_eval.forward(batch.images.to_device(device));
+        let logits = model.forward(batch.images().clone());
+        let loss = ce.forward(logits.clone(), batch.targets().clone());
         let preds = logits.argmax(1).reshape([-1]);
-        let eq = preds.equal(batch.targets.to_device(device));
+        let eq = preds.equal(batch.targets().clone());
         let batch_size = eq.dims()[0];
-        let correct_batch = eq.int().sum().into_data().to_vec::<i32>().expect("sum")[0] as usize;
+        let correct_batch = eq.int().sum().into_data().iter::<i64>().next().expect("sum") as usize;
         correct += correct_batch;
         total += batch_size;
+        loss_sum += loss.into_scalar().elem::<f32>() * batch_size as f32;
     }
-    ((correct as f32) / (total.max(1) as f32), total)
+    let total_f = total.max(1) as f32;
+    ((correct as f32) / total_f, loss_sum / total_f, total)
 }
 
-#[allow(dead_code)]
-fn evaluate_cifar10<Bx, ADx>(
-    model_ad: &CifarNet<ADx>,
-    loader: &std::sync::Arc<dyn burn::data::dataloader::DataLoader<Bx, CifarBatch<Bx>>>,
-    device: &Bx::Device,
-) -> (f32, usize)
-where
-    Bx: Backend,
-    ADx: AutodiffBackend<InnerBackend = Bx>,
-{
-    let model_eval: CifarNet<Bx> = model_ad.clone().valid();
+#[cfg(all(test, feature = "ndarray"))]
+mod tests {
+    use super::*;
+    use crate::augment::lock_backend_rng;
+    use crate::data::{CifarBatch, CifarItem};
+    use burn::backend::NdArray;
+    use burn::data::dataset::InMemDataset;
+
+    type B = NdArray;
+
+    #[test]
+    fn resumed_epoch_sees_the_same_augmented_batches() {
+        let config: DatasetConfig = serde_json::from_str(include_str!("../configs/cifar10.json")).expect("config");
+        let items: Vec<CifarItem> = (0..12)
+            .map(|i| {
+                let mut image = [[[0.0f32; 32]; 32]; 3];
+                for (c, plane) in image.iter_mut().enumerate() {
+                    for (y, row) in plane.iter_mut().enumerate() {
+                        for (x, pixel) in row.iter_mut().enumerate() {
+                            *pixel = ((i * 7 + c * 31 + y * 5 + x * 3) % 256) as f32;
+                        }
+                    }
+                }
+                CifarItem { image, label: i % 10 }
+            })
+            .collect();
+        // シャッフル順は iter() ごとに進むので、実行ごとに作り直す
+        let loader = || -> Arc<dyn DataLoader<B, CifarBatch<B>>> {
+            DataLoaderBuilder::new(CifarBatcher::new(&config).unwrap().with_augmentation(&config))
+                .batch_size(2)
+                .shuffle(SEED)
+                .build(InMemDataset::new(items.clone()))
+        };
+        let images = |batch: CifarBatch<B>| batch.images.into_data().to_vec::<f32>().unwrap();
+        let _rng = lock_backend_rng();
+
+        // 中断なし: step 2 でチェックポイントを保存して再シードする
+        let mut state = TrainState::new(SEED);
+        B::seed(state.rng_seed());
+        let mut uninterrupted = Vec::new();
+        let mut saved = None;
+        let first_run = loader();
+        for batch in train_batches(first_run.as_ref(), &state) {
+            uninterrupted.push(images(batch));
+            state.step(0.0);
+            if state.global_step == 2 {
+                saved = Some(state.clone());
+                B::seed(state.rng_seed());
+            }
+        }
 
-    let mut correct = 0usize;
-    let mut total = 0usize;
+        // step 2 のチェックポイントから再開
+        let saved = saved.unwrap();
+        B::seed(saved.rng_seed());
+        let second_run = loader();
+        let resumed: Vec<Vec<f32>> = train_batches(second_run.as_ref(), &saved).map(images).collect();
+        assert_eq!(resumed.len(), 4);
+        assert!(resumed == uninterrupted[2..], "resumed batches differ");
+    }
 
-    for batch in loader.iter() {
-        let logits = model_eval.forward(batch.images.to_device(device));
-        let preds = logits.argmax(1).reshape([-1]);
-        let eq = preds.equal(batch.targets.to_device(device));
-        let batch_size = eq.dims()[0];
-        let correct_batch = eq.int().sum().into_data().to_vec::<i32>().expect("sum")[0] as usize;
-        correct += correct_batch;
-        total += batch_size;
+    #[test]
+    fn resume_rewrites_model_files_from_the_checkpoint_best() {
+        use burn::nn::{Linear, LinearConfig};
+
+        let dir = std::env::temp_dir().join(format!("train-resume-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let mut config: DatasetConfig = serde_json::from_str(include_str!("../configs/cifar10.json")).expect("config");
+        config.artifacts.dir = dir.to_string_lossy().into_owned();
+        fs::create_dir_all(&dir).unwrap();
+        let checkpointer = Checkpointer::new(&config);
+        let device = Default::default();
+        let _rng = lock_backend_rng();
+
+        // epoch 2 が最良のまま epoch 2 の終わりで保存したチェックポイント
+        let model: Linear<Autodiff<B>> = LinearConfig::new(4, 3).init(&device);
+        let optim = AdamConfig::new().init::<Autodiff<B>, Linear<Autodiff<B>>>();
+        let mut state = TrainState::new(SEED);
+        state.step(1.0);
+        state.finish_epoch(0.4, 0.0);
+        state.step(1.0);
+        state.finish_epoch(0.5, 0.0);
+        let best = model.valid();
+        save_model(&config, best.clone()).unwrap();
+        let path = checkpointer.save(&model, Some(&best), &optim, &state).unwrap();
+
+        // 放棄した実行の epoch 3 がより良いモデルで model.burn / model.bin を上書きした
+        let abandoned: Linear<B> = LinearConfig::new(4, 3).init(&device);
+        save_model(&config, abandoned.clone()).unwrap();
+
+        let template: Linear<B> = LinearConfig::new(4, 3).init(&device);
+        let restored = restore_best_model(&config, &checkpointer, &path, &state, template.clone(), &device).unwrap();
+        let weights = |model: &Linear<B>| model.weight.val().into_data().to_vec::<f32>().unwrap();
+        assert_eq!(weights(&restored.unwrap()), weights(&best));
+
+        let bytes = fs::read(config.get_model_bin_path()).unwrap();
+        let record = BinBytesRecorder::<FullPrecisionSettings, Vec<u8>>::default()
+            .load(bytes, &device)
+            .unwrap();
+        assert_eq!(weights(&template.clone().load_record(record)), weights(&best));
+        // model.burn は半精度で保存される
+        let shipped = template
+            .load_file(config.get_model_path(), &CompactRecorder::new(), &device)
+            .unwrap();
+        let close = weights(&shipped)
+            .iter()
+            .zip(weights(&best))
+            .all(|(a, b)| (a - b).abs() < 1e-2);
+        assert!(close, "model.burn is not the checkpoint's best model");
+        assert_ne!(weights(&abandoned), weights(&best));
+
+        // 最良のエポックがまだないチェックポイントには最良モデルがない
+        let first = checkpointer.save(&model, None, &optim, &TrainState::new(SEED)).unwrap();
+        let none = checkpointer.load_best(&first, LinearConfig::new(4, 3).init::<B>(&device), &device).unwrap();
+        assert!(none.is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
     }
-    ((correct as f32) / (total.max(1) as f32), total)
 }
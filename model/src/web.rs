@@ -8,8 +8,9 @@ use js_sys::Array;
 #[cfg(target_family = "wasm")]
 use wasm_bindgen::prelude::*;
 
-use crate::model::{LeNet, ModelTrait};
-use crate::state::build_and_load_model_legacy;
+use crate::model::ModelTrait;
+use crate::preprocess::{PixelLayout, Preprocessor};
+use crate::state::{ModelInstance, build_and_load_model};
 // (record loading temporarily disabled until proper deserialization path implemented)
 type Backend = burn_wgpu::Wgpu;
 
@@ -27,7 +28,8 @@ pub fn start() {
 /// See:[exporting-rust-struct](https://rustwasm.github.io/wasm-bindgen/contributing/design/exporting-rust-struct.html)
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 pub struct Mnist {
-    model: Option<LeNet<Backend>>,
+    model: Option<ModelInstance>,
+    preprocessor: Option<Preprocessor>,
 }
 
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
@@ -37,7 +39,10 @@ impl Mnist {
     pub fn new() -> Self {
         #[cfg(target_arch = "wasm32")]
         console_error_panic_hook::set_once();
-        Self { model: None }
+        Self {
+            model: None,
+            preprocessor: None,
+        }
     }
 
     /// Returns the inference results.
@@ -58,18 +63,14 @@ impl Mnist {
         }
 
         let model = self.model.as_ref().unwrap();
+        let preprocessor = self.preprocessor.as_ref().unwrap();
 
         let device = Default::default();
-        // Reshape from the 1D array to 3d tensor [batch, height, width]
-        let input = Tensor::<Backend, 1>::from_floats(input, &device)
-            .reshape([1usize, 28, 28]) // [B=1,H,W]
-            .reshape([1usize, 1, 28, 28]); // [B,C=1,H,W]
-
-        // Normalize input: make between [0,1] and make the mean=0 and std=1
-        // values mean=0.1307,std=0.3081 were copied from Pytorch Mist Example
-        // https://github.com/pytorch/examples/blob/54f4572509891883a947411fd7239237dd2a39c3/mnist/main.py#L122
-
-        let input = ((input / 255) - 0.1307) / 0.3081;
+        // Reshape from the 1D array to [batch, channel, height, width] and normalize it
+        // with the mean/std from configs/mnist.json (same as training)
+        let input = preprocessor
+            .pixels_f32::<Backend>(input, PixelLayout::Chw, &device)
+            .map_err(|e| e.to_string())?;
 
         // Run the tensor input through the model
         let output: Tensor<Backend, 2> = model.forward(input);
@@ -94,7 +95,9 @@ impl Mnist {
         if self.model.is_some() {
             return Ok(());
         }
-        self.model = Some(build_and_load_model_legacy().await);
+        let (model, config) = build_and_load_model("mnist").await?;
+        self.preprocessor = Some(Preprocessor::new(&config).map_err(|e| e.to_string())?);
+        self.model = Some(model);
         Ok(())
     }
 
@@ -111,11 +114,11 @@ impl Mnist {
             self.load().await?;
         }
         let model = self.model.as_ref().ok_or("model not loaded")?;
+        let preprocessor = self.preprocessor.as_ref().ok_or("model not loaded")?;
         let device = Default::default();
-        let input = Tensor::<Backend, 1>::from_floats(input, &device)
-            .reshape([1usize, 28, 28])
-            .reshape([1usize, 1, 28, 28]);
-        let input = ((input / 255) - 0.1307) / 0.3081;
+        let input = preprocessor
+            .pixels_f32::<Backend>(input, PixelLayout::Chw, &device)
+            .map_err(|e| e.to_string())?;
         let output: Tensor<Backend, 2> = model.forward(input);
         let pred = output.argmax(1).into_data_async().await;
         let mut class_id: u32 = 0;
@@ -8,25 +8,10 @@ use js_sys::Array;
 use wasm_bindgen::prelude::*;
 
 use crate::config::DatasetConfig;
-use crate::model::{CifarNet, ModelTrait};
-// CIFAR-10クラス名の定義（WASM用）
-const CIFAR10_CLASSES: [&str; 10] = [
-    "airplane",
-    "automobile",
-    "bird",
-    "cat",
-    "deer",
-    "dog",
-    "frog",
-    "horse",
-    "ship",
-    "truck",
-];
-use burn::{
-    module::Module,
-    record::{BinBytesRecorder, FullPrecisionSettings, Recorder},
-    tensor::Tensor,
-};
+use crate::model::{self, ModelTrait};
+use crate::preprocess::{PixelLayout, Preprocessor};
+use crate::registry::{self, ModelSource};
+use burn::tensor::Tensor;
 use burn_wgpu::{WgpuDevice, graphics::AutoGraphicsApi, init_setup_async};
 
 type Backend = burn_wgpu::Wgpu;
@@ -44,25 +29,33 @@ pub fn start() {
 /// CIFAR-10専用の推論クラス
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 pub struct Cifar10Model {
-    model: Option<CifarNet<Backend>>,
+    model: Option<Box<dyn ModelTrait<Backend>>>,
     config: DatasetConfig,
+    preprocessor: Preprocessor,
 }
 
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 impl Cifar10Model {
     /// コンストラクタ
+    ///
+    /// 埋め込みの設定が不正なら (CLI の `load` と同じ `validate` で確認) JS に例外を投げる。
     #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, JsValue> {
         #[cfg(target_arch = "wasm32")]
         console_error_panic_hook::set_once();
 
+        let error = |what: &str, e: &dyn core::fmt::Display| JsValue::from_str(&format!("CIFAR-10{what}: {e}"));
         let config: DatasetConfig =
-            serde_json::from_str(CIFAR10_CONFIG).expect("CIFAR-10設定の解析に失敗");
+            serde_json::from_str(CIFAR10_CONFIG).map_err(|e| error("設定の解析に失敗", &e))?;
+        config.validate().map_err(|e| error("設定が不正です", &e))?;
 
-        Self {
+        let preprocessor = Preprocessor::new(&config).map_err(|e| error("前処理の設定に失敗", &e))?;
+
+        Ok(Self {
             model: None,
             config,
-        }
+            preprocessor,
+        })
     }
 
     /// モデルをロード
@@ -75,13 +68,14 @@ impl Cifar10Model {
         init_setup_async::<AutoGraphicsApi>(&WgpuDevice::default(), Default::default()).await;
 
         let device = WgpuDevice::default();
-        let model: CifarNet<Backend> = CifarNet::new(&device, &self.config);
-
-        let record = BinBytesRecorder::<FullPrecisionSettings, &'static [u8]>::default()
-            .load(CIFAR10_STATE_ENCODED, &device)
-            .map_err(|e| format!("CIFAR-10モデルのロードに失敗: {}", e))?;
-
-        self.model = Some(model.load_record(record));
+        let model = registry::load_model::<Backend>(
+            &self.config,
+            &device,
+            ModelSource::Bytes(CIFAR10_STATE_ENCODED),
+        )
+        .map_err(|e| format!("CIFAR-10モデルのロードに失敗: {}", e))?;
+
+        self.model = Some(model);
         Ok(())
     }
 
@@ -95,13 +89,11 @@ impl Cifar10Model {
         let model = self.model.as_ref().unwrap();
         let device = WgpuDevice::default();
 
-        // 入力テンソルの準備（32x32x3のRGB画像）
-        let input = Tensor::<Backend, 1>::from_floats(input, &device)
-            .reshape([3, 32, 32])
-            .reshape([1usize, 3, 32, 32]);
-
-        // CIFAR-10正規化（[0,1]範囲）
-        let input = input / 255.0;
+        // 入力テンソルの準備 (0-255 の CHW 形式 32x32x3 を設定に従って正規化)
+        let input = self
+            .preprocessor
+            .pixels_f32::<Backend>(input, PixelLayout::Chw, &device)
+            .map_err(|e| e.to_string())?;
 
         // 推論実行
         let output: Tensor<Backend, 2> = model.forward(input);
@@ -116,6 +108,38 @@ impl Cifar10Model {
         Ok(array)
     }
 
+    /// MC Dropout 推論 (Dropout を有効にしたまま `samples` 回推論した平均確率と予測エントロピー)
+    #[cfg_attr(target_family = "wasm", wasm_bindgen(js_name = "inferenceMcDropout"))]
+    pub async fn inference_mc_dropout(&mut self, input: &[f32], samples: u32) -> Result<McDropoutResult, String> {
+        if self.model.is_none() {
+            self.load().await?;
+        }
+
+        let model = self.model.as_ref().unwrap();
+        if !model.has_dropout() {
+            return Err("Dropout のないモデルでは MC Dropout 推論はできません".to_string());
+        }
+        let device = WgpuDevice::default();
+
+        let input = self
+            .preprocessor
+            .pixels_f32::<Backend>(input, PixelLayout::Chw, &device)
+            .map_err(|e| e.to_string())?;
+
+        let output = model::mc_dropout_probs(model.as_ref(), input, samples as usize);
+        let probabilities = output
+            .into_data_async()
+            .await
+            .to_vec::<f32>()
+            .map_err(|e| format!("確率の取得に失敗: {:?}", e))?;
+        let entropy = model::predictive_entropy(&probabilities);
+
+        Ok(McDropoutResult {
+            probabilities,
+            entropy,
+        })
+    }
+
     /// Top-1予測クラスのみ返す
     #[cfg_attr(target_family = "wasm", wasm_bindgen(js_name = "inferenceTop1"))]
     pub async fn inference_top1(&mut self, input: &[f32]) -> Result<u32, String> {
@@ -126,10 +150,10 @@ impl Cifar10Model {
         let model = self.model.as_ref().unwrap();
         let device = WgpuDevice::default();
 
-        let input = Tensor::<Backend, 1>::from_floats(input, &device)
-            .reshape([3, 32, 32])
-            .reshape([1usize, 3, 32, 32]);
-        let input = input / 255.0;
+        let input = self
+            .preprocessor
+            .pixels_f32::<Backend>(input, PixelLayout::Chw, &device)
+            .map_err(|e| e.to_string())?;
 
         let output: Tensor<Backend, 2> = model.forward(input);
         let pred = output.argmax(1).into_data_async().await;
@@ -141,9 +165,10 @@ impl Cifar10Model {
     /// 予測クラス名を返す
     #[cfg_attr(target_family = "wasm", wasm_bindgen(js_name = "getClassName"))]
     pub fn get_class_name(&self, class_id: u32) -> String {
-        CIFAR10_CLASSES
+        self.config
+            .class_names
             .get(class_id as usize)
-            .unwrap_or(&"unknown")
+            .map_or("unknown", |name| name.as_str())
             .to_string()
     }
 
@@ -157,9 +182,31 @@ impl Cifar10Model {
     #[cfg_attr(target_family = "wasm", wasm_bindgen(js_name = "getClassNames"))]
     pub fn get_class_names(&self) -> Array {
         let array = Array::new();
-        for class_name in CIFAR10_CLASSES.iter() {
+        for class_name in &self.config.class_names {
             array.push(&JsValue::from_str(class_name));
         }
         array
     }
 }
+
+/// MC Dropout 推論の結果
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+pub struct McDropoutResult {
+    probabilities: Vec<f32>,
+    entropy: f32,
+}
+
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+impl McDropoutResult {
+    /// クラスごとの平均確率
+    #[cfg_attr(target_family = "wasm", wasm_bindgen(getter))]
+    pub fn probabilities(&self) -> Vec<f32> {
+        self.probabilities.clone()
+    }
+
+    /// 予測エントロピー (nats, 大きいほど不確か)
+    #[cfg_attr(target_family = "wasm", wasm_bindgen(getter))]
+    pub fn entropy(&self) -> f32 {
+        self.entropy
+    }
+}
@@ -8,12 +8,10 @@ use js_sys::Array;
 use wasm_bindgen::prelude::*;
 
 use crate::config::DatasetConfig;
-use crate::model::{LeNet, ModelTrait};
-use burn::{
-    module::Module,
-    record::{BinBytesRecorder, FullPrecisionSettings, Recorder},
-    tensor::Tensor,
-};
+use crate::model::ModelTrait;
+use crate::preprocess::{PixelLayout, Preprocessor};
+use crate::registry::{self, ModelSource};
+use burn::tensor::Tensor;
 use burn_wgpu::{WgpuDevice, graphics::AutoGraphicsApi, init_setup_async};
 
 type Backend = burn_wgpu::Wgpu;
@@ -31,25 +29,33 @@ pub fn start() {
 /// MNIST専用の推論クラス
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 pub struct MnistModel {
-    model: Option<LeNet<Backend>>,
+    model: Option<Box<dyn ModelTrait<Backend>>>,
     config: DatasetConfig,
+    preprocessor: Preprocessor,
 }
 
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 impl MnistModel {
     /// コンストラクタ
+    ///
+    /// 埋め込みの設定が不正なら (CLI の `load` と同じ `validate` で確認) JS に例外を投げる。
     #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
-    pub fn new() -> Self {
+    pub fn new() -> Result<Self, JsValue> {
         #[cfg(target_arch = "wasm32")]
         console_error_panic_hook::set_once();
 
+        let error = |what: &str, e: &dyn core::fmt::Display| JsValue::from_str(&format!("MNIST{what}: {e}"));
         let config: DatasetConfig =
-            serde_json::from_str(MNIST_CONFIG).expect("MNIST設定の解析に失敗");
+            serde_json::from_str(MNIST_CONFIG).map_err(|e| error("設定の解析に失敗", &e))?;
+        config.validate().map_err(|e| error("設定が不正です", &e))?;
 
-        Self {
+        let preprocessor = Preprocessor::new(&config).map_err(|e| error("前処理の設定に失敗", &e))?;
+
+        Ok(Self {
             model: None,
             config,
-        }
+            preprocessor,
+        })
     }
 
     /// モデルをロード
@@ -62,13 +68,14 @@ impl MnistModel {
         init_setup_async::<AutoGraphicsApi>(&WgpuDevice::default(), Default::default()).await;
 
         let device = WgpuDevice::default();
-        let model: LeNet<Backend> = LeNet::new(&device, &self.config);
-
-        let record = BinBytesRecorder::<FullPrecisionSettings, &'static [u8]>::default()
-            .load(MNIST_STATE_ENCODED, &device)
-            .map_err(|e| format!("MNISTモデルのロードに失敗: {}", e))?;
-
-        self.model = Some(model.load_record(record));
+        let model = registry::load_model::<Backend>(
+            &self.config,
+            &device,
+            ModelSource::Bytes(MNIST_STATE_ENCODED),
+        )
+        .map_err(|e| format!("MNISTモデルのロードに失敗: {}", e))?;
+
+        self.model = Some(model);
         Ok(())
     }
 
@@ -82,13 +89,11 @@ impl MnistModel {
         let model = self.model.as_ref().unwrap();
         let device = WgpuDevice::default();
 
-        // 入力テンソルの準備
-        let input = Tensor::<Backend, 1>::from_floats(input, &device)
-            .reshape([1usize, 28, 28])
-            .reshape([1usize, 1, 28, 28]);
-
-        // MNIST正規化
-        let input = ((input / 255.0) - 0.1307) / 0.3081;
+        // 入力テンソルの準備 (0-255 のグレースケール 28x28 を設定に従って正規化)
+        let input = self
+            .preprocessor
+            .pixels_f32::<Backend>(input, PixelLayout::Chw, &device)
+            .map_err(|e| e.to_string())?;
 
         // 推論実行
         let output: Tensor<Backend, 2> = model.forward(input);
@@ -113,10 +118,10 @@ impl MnistModel {
         let model = self.model.as_ref().unwrap();
         let device = WgpuDevice::default();
 
-        let input = Tensor::<Backend, 1>::from_floats(input, &device)
-            .reshape([1usize, 28, 28])
-            .reshape([1usize, 1, 28, 28]);
-        let input = ((input / 255.0) - 0.1307) / 0.3081;
+        let input = self
+            .preprocessor
+            .pixels_f32::<Backend>(input, PixelLayout::Chw, &device)
+            .map_err(|e| e.to_string())?;
 
         let output: Tensor<Backend, 2> = model.forward(input);
         let pred = output.argmax(1).into_data_async().await;